@@ -1,9 +1,17 @@
 use std::num::TryFromIntError;
 
+#[cfg(feature = "spend-cost")]
+use chia_consensus::gen::validation_error::ErrorCode;
+use chia_protocol::Bytes32;
 use clvm_traits::{FromClvmError, ToClvmError};
+#[cfg(feature = "experimental-vaults")]
+use clvm_utils::TreeHash;
 use clvmr::reduction::EvalErr;
 use thiserror::Error;
 
+#[cfg(feature = "experimental-vaults")]
+use crate::RestrictionViolation;
+
 #[derive(Debug, Error)]
 pub enum DriverError {
     #[error("io error: {0}")]
@@ -59,4 +67,63 @@ pub enum DriverError {
 
     #[error("missing spend for vault subpath")]
     MissingSubpathSpend,
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("restriction on vault member {member_hash:?} was violated: {violation}")]
+    RestrictionViolated {
+        member_hash: TreeHash,
+        violation: RestrictionViolation,
+    },
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("m of n requires {required} of {total} members, so it can never be spent")]
+    InvalidMofN { required: usize, total: usize },
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("custody configuration does not match the vault")]
+    CustodyMismatch,
+
+    #[error("recovery list does not match the DID")]
+    InvalidRecoveryList,
+
+    #[error("attestation is not for this recovery")]
+    InvalidAttestation,
+
+    #[error("expected at least {required} attestations, but found {found}")]
+    MissingAttestations { found: u64, required: u64 },
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("cannot spend {amount} mojos with a rate limit of {limit}")]
+    RateLimitExceeded { amount: u64, limit: u64 },
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("expected an even amount to spend with a rate limit, but it was {0}")]
+    OddRateLimitAmount(u64),
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("missing synthetic key for p2 puzzle hash {0}")]
+    MissingSyntheticKey(Bytes32),
+
+    #[cfg(feature = "offers")]
+    #[error("royalty amount overflow")]
+    RoyaltyOverflow,
+
+    #[error("expected metadata updater puzzle hash {expected}, but found {found}")]
+    MetadataUpdaterMismatch { expected: Bytes32, found: Bytes32 },
+
+    #[cfg(feature = "spend-cost")]
+    #[error("validation error: {0:?}")]
+    Validation(ErrorCode),
+
+    #[cfg(feature = "spend-cost")]
+    #[error("cost of {cost} exceeds the maximum of {max_cost}")]
+    CostExceeded { cost: u64, max_cost: u64 },
+
+    #[cfg(feature = "spend-cost")]
+    #[error("expected at least {required} mojos of funding, but found {found}")]
+    InsufficientFunding { found: u64, required: u64 },
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("fee coin {0} is also being migrated")]
+    FeeCoinMigrated(Bytes32),
 }
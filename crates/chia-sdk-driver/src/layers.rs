@@ -1,4 +1,5 @@
 mod augmented_condition_layer;
+mod cat1_layer;
 mod cat_layer;
 mod did_layer;
 mod nft_ownership_layer;
@@ -14,6 +15,7 @@ mod singleton_layer;
 mod standard_layer;
 
 pub use augmented_condition_layer::*;
+pub use cat1_layer::*;
 pub use cat_layer::*;
 pub use did_layer::*;
 pub use nft_ownership_layer::*;
@@ -33,3 +35,9 @@ mod datalayer;
 
 #[cfg(feature = "chip-0035")]
 pub use datalayer::*;
+
+#[cfg(feature = "experimental-vaults")]
+mod p2_singleton_message_layer;
+
+#[cfg(feature = "experimental-vaults")]
+pub use p2_singleton_message_layer::*;
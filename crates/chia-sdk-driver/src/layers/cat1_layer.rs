@@ -0,0 +1,164 @@
+use chia_protocol::Bytes32;
+use chia_puzzles::cat::{CatArgs, CatSolution, CAT_PUZZLE_HASH_V1, CAT_PUZZLE_V1};
+use clvm_traits::FromClvm;
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{DriverError, Layer, Puzzle, SpendContext};
+
+/// The CAT1 [`Layer`] is the original version of the CAT standard, which has been replaced by CAT2.
+/// It's only supported so that legacy coins can be parsed and melted, such as during a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cat1Layer<I> {
+    /// The asset id of the CAT1 token. This is the tree hash of the TAIL program.
+    pub asset_id: Bytes32,
+    /// The inner puzzle layer, commonly used for determining ownership.
+    pub inner_puzzle: I,
+}
+
+impl<I> Cat1Layer<I> {
+    pub fn new(asset_id: Bytes32, inner_puzzle: I) -> Self {
+        Self {
+            asset_id,
+            inner_puzzle,
+        }
+    }
+}
+
+impl<I> Layer for Cat1Layer<I>
+where
+    I: Layer,
+{
+    type Solution = CatSolution<I::Solution>;
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if puzzle.mod_hash != CAT_PUZZLE_HASH_V1 {
+            return Ok(None);
+        }
+
+        let args = CatArgs::<NodePtr>::from_clvm(allocator, puzzle.args)?;
+
+        if args.mod_hash != CAT_PUZZLE_HASH_V1.into() {
+            return Err(DriverError::InvalidModHash);
+        }
+
+        let Some(inner_puzzle) =
+            I::parse_puzzle(allocator, Puzzle::parse(allocator, args.inner_puzzle))?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            asset_id: args.asset_id,
+            inner_puzzle,
+        }))
+    }
+
+    fn parse_solution(
+        allocator: &Allocator,
+        solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        let solution = CatSolution::<NodePtr>::from_clvm(allocator, solution)?;
+        let inner_solution = I::parse_solution(allocator, solution.inner_puzzle_solution)?;
+        Ok(CatSolution {
+            inner_puzzle_solution: inner_solution,
+            lineage_proof: solution.lineage_proof,
+            prev_coin_id: solution.prev_coin_id,
+            this_coin_info: solution.this_coin_info,
+            next_coin_proof: solution.next_coin_proof,
+            prev_subtotal: solution.prev_subtotal,
+            extra_delta: solution.extra_delta,
+        })
+    }
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let inner_puzzle = self.inner_puzzle.construct_puzzle(ctx)?;
+        let mod_ptr = ctx.puzzle(CAT_PUZZLE_HASH_V1, &CAT_PUZZLE_V1)?;
+        ctx.alloc(&CurriedProgram {
+            program: mod_ptr,
+            args: CatArgs {
+                mod_hash: CAT_PUZZLE_HASH_V1.into(),
+                asset_id: self.asset_id,
+                inner_puzzle,
+            },
+        })
+    }
+
+    fn construct_solution(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        let inner_solution = self
+            .inner_puzzle
+            .construct_solution(ctx, solution.inner_puzzle_solution)?;
+        ctx.alloc(&CatSolution {
+            inner_puzzle_solution: inner_solution,
+            lineage_proof: solution.lineage_proof,
+            prev_coin_id: solution.prev_coin_id,
+            this_coin_info: solution.this_coin_info,
+            next_coin_proof: solution.next_coin_proof,
+            prev_subtotal: solution.prev_subtotal,
+            extra_delta: solution.extra_delta,
+        })
+    }
+}
+
+impl<I> ToTreeHash for Cat1Layer<I>
+where
+    I: ToTreeHash,
+{
+    fn tree_hash(&self) -> TreeHash {
+        cat1_puzzle_hash(self.asset_id, self.inner_puzzle.tree_hash())
+    }
+}
+
+/// Calculates the puzzle hash of a CAT1 coin, given its asset id and inner puzzle hash.
+pub fn cat1_puzzle_hash(asset_id: Bytes32, inner_puzzle_hash: TreeHash) -> TreeHash {
+    CurriedProgram {
+        program: CAT_PUZZLE_HASH_V1,
+        args: CatArgs {
+            mod_hash: CAT_PUZZLE_HASH_V1.into(),
+            asset_id,
+            inner_puzzle: inner_puzzle_hash,
+        },
+    }
+    .tree_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::cat::CAT_PUZZLE_HASH;
+
+    use super::*;
+
+    use crate::CatLayer;
+
+    #[test]
+    fn test_cat1_layer() -> anyhow::Result<()> {
+        let mut ctx = SpendContext::new();
+        let asset_id = Bytes32::new([1; 32]);
+
+        let layer = Cat1Layer::new(asset_id, "Hello, world!".to_string());
+
+        let ptr = layer.construct_puzzle(&mut ctx)?;
+        let puzzle = Puzzle::parse(&ctx.allocator, ptr);
+        let roundtrip =
+            Cat1Layer::<String>::parse_puzzle(&ctx.allocator, puzzle)?.expect("invalid CAT1 layer");
+
+        assert_eq!(roundtrip.asset_id, layer.asset_id);
+        assert_eq!(roundtrip.inner_puzzle, layer.inner_puzzle);
+        assert_eq!(ctx.tree_hash(ptr), layer.tree_hash());
+
+        // CAT1 and CAT2 puzzles must not be mistaken for each other.
+        assert_eq!(puzzle.mod_hash(), CAT_PUZZLE_HASH_V1);
+        assert_ne!(puzzle.mod_hash(), CAT_PUZZLE_HASH);
+        assert!(CatLayer::<String>::parse_puzzle(&ctx.allocator, puzzle)?.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::singleton::{SingletonStruct, SINGLETON_TOP_LAYER_PUZZLE_HASH};
+use chia_sdk_types::{
+    Conditions, Mod, P2SingletonMessageArgs, P2SingletonMessageSolution,
+    P2_SINGLETON_MESSAGE_PUZZLE_HASH, PUZZLE_TO_COIN_MESSAGE_MODE,
+};
+use clvm_traits::FromClvm;
+use clvm_utils::{ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{DriverError, Layer, Puzzle, Spend, SpendContext};
+
+/// The p2 singleton message [`Layer`] allows for requiring that a singleton
+/// be spent alongside this coin, sending it a message to authorize the spend.
+///
+/// The message is the tree hash of the delegated puzzle, which outputs the conditions of this coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct P2SingletonMessageLayer {
+    /// The tree hash of the singleton struct, since the launcher id isn't revealed by the puzzle.
+    pub singleton_struct_hash: Bytes32,
+}
+
+impl P2SingletonMessageLayer {
+    pub fn new(launcher_id: Bytes32) -> Self {
+        Self {
+            singleton_struct_hash: SingletonStruct::new(launcher_id).tree_hash().into(),
+        }
+    }
+
+    /// Creates a spend that outputs the conditions of the delegated spend.
+    pub fn spend(
+        &self,
+        ctx: &mut SpendContext,
+        singleton_inner_puzzle_hash: Bytes32,
+        delegated_spend: Spend,
+    ) -> Result<Spend, DriverError> {
+        let puzzle = self.construct_puzzle(ctx)?;
+        let solution = self.construct_solution(
+            ctx,
+            P2SingletonMessageSolution::new(
+                singleton_inner_puzzle_hash,
+                delegated_spend.puzzle,
+                delegated_spend.solution,
+            ),
+        )?;
+        Ok(Spend::new(puzzle, solution))
+    }
+
+    /// Creates a spend that outputs the given conditions, along with the message
+    /// condition that the singleton must output to authorize it.
+    pub fn spend_with_message(
+        &self,
+        ctx: &mut SpendContext,
+        coin_id: Bytes32,
+        singleton_inner_puzzle_hash: Bytes32,
+        conditions: Conditions,
+    ) -> Result<(Spend, Conditions), DriverError> {
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+        let message = ctx.tree_hash(delegated_spend.puzzle);
+
+        let spend = self.spend(ctx, singleton_inner_puzzle_hash, delegated_spend)?;
+
+        let data = vec![ctx.alloc(&coin_id)?];
+        let send_message = Conditions::new().send_message(
+            PUZZLE_TO_COIN_MESSAGE_MODE,
+            message.to_vec().into(),
+            data,
+        );
+
+        Ok((spend, send_message))
+    }
+
+    /// Spends a coin with the given conditions, and returns the message
+    /// condition that the singleton must output to authorize it.
+    pub fn spend_coin(
+        &self,
+        ctx: &mut SpendContext,
+        coin: Coin,
+        singleton_inner_puzzle_hash: Bytes32,
+        conditions: Conditions,
+    ) -> Result<Conditions, DriverError> {
+        let (spend, send_message) =
+            self.spend_with_message(ctx, coin.coin_id(), singleton_inner_puzzle_hash, conditions)?;
+        ctx.spend(coin, spend)?;
+        Ok(send_message)
+    }
+}
+
+impl Layer for P2SingletonMessageLayer {
+    type Solution = P2SingletonMessageSolution<NodePtr, NodePtr>;
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if puzzle.mod_hash != P2_SINGLETON_MESSAGE_PUZZLE_HASH {
+            return Ok(None);
+        }
+
+        let args = P2SingletonMessageArgs::from_clvm(allocator, puzzle.args)?;
+
+        if args.singleton_mod_hash != SINGLETON_TOP_LAYER_PUZZLE_HASH.into() {
+            return Err(DriverError::InvalidSingletonStruct);
+        }
+
+        Ok(Some(Self {
+            singleton_struct_hash: args.singleton_struct_hash,
+        }))
+    }
+
+    fn parse_solution(
+        allocator: &Allocator,
+        solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        Ok(P2SingletonMessageSolution::from_clvm(allocator, solution)?)
+    }
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        ctx.curry(P2SingletonMessageArgs {
+            singleton_mod_hash: SINGLETON_TOP_LAYER_PUZZLE_HASH.into(),
+            singleton_struct_hash: self.singleton_struct_hash,
+        })
+    }
+
+    fn construct_solution(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        ctx.alloc(&solution)
+    }
+}
+
+impl ToTreeHash for P2SingletonMessageLayer {
+    fn tree_hash(&self) -> TreeHash {
+        P2SingletonMessageArgs {
+            singleton_mod_hash: SINGLETON_TOP_LAYER_PUZZLE_HASH.into(),
+            singleton_struct_hash: self.singleton_struct_hash,
+        }
+        .curry_tree_hash()
+    }
+}
@@ -25,3 +25,9 @@ mod offers;
 
 #[cfg(feature = "offers")]
 pub use offers::*;
+
+#[cfg(feature = "spend-cost")]
+mod spend_cost;
+
+#[cfg(feature = "spend-cost")]
+pub use spend_cost::*;
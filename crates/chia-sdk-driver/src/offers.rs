@@ -4,6 +4,7 @@ mod error;
 mod offer;
 mod offer_builder;
 mod parsed_offer;
+mod royalties;
 
 pub use compress::*;
 pub use encode::*;
@@ -11,6 +12,7 @@ pub use error::*;
 pub use offer::*;
 pub use offer_builder::*;
 pub use parsed_offer::*;
+pub use royalties::*;
 
 #[cfg(test)]
 mod tests;
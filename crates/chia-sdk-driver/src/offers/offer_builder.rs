@@ -6,7 +6,7 @@ use clvm_utils::tree_hash;
 use clvmr::Allocator;
 use indexmap::IndexMap;
 
-use crate::{DriverError, Offer, ParsedOffer, Puzzle, SpendContext};
+use crate::{CatLayer, DriverError, Layer, NftRoyalties, Offer, ParsedOffer, Puzzle, SpendContext};
 
 #[derive(Debug, Clone)]
 pub struct OfferBuilder<T> {
@@ -86,6 +86,32 @@ impl OfferBuilder<Make> {
         Ok(self)
     }
 
+    /// Requests the royalty payments of the offered NFTs, which the taker pays in
+    /// addition to the requested assets. Each payment uses the NFT's launcher id as the nonce.
+    pub fn request_royalties(
+        mut self,
+        ctx: &mut SpendContext,
+        royalties: &[NftRoyalties],
+    ) -> Result<Self, DriverError> {
+        for payment in royalties.iter().flat_map(|royalties| &royalties.payments) {
+            let settlement = ctx.settlement_payments_puzzle()?;
+
+            let puzzle = match payment.asset_id {
+                Some(asset_id) => CatLayer::new(asset_id, settlement).construct_puzzle(ctx)?,
+                None => settlement,
+            };
+
+            self = self.request_with_nonce(
+                ctx,
+                &puzzle,
+                payment.notarized_payment.nonce,
+                payment.notarized_payment.payments.clone(),
+            )?;
+        }
+
+        Ok(self)
+    }
+
     /// This will create a new [`OfferBuilder`] with the requested payments frozen.
     /// It returns a list of announcements that can be asserted by the maker side.
     pub fn finish(self) -> (Vec<AssertPuzzleAnnouncement>, OfferBuilder<Partial>) {
@@ -0,0 +1,296 @@
+use chia_protocol::Bytes32;
+use chia_puzzles::{
+    cat::CatArgs,
+    offer::{NotarizedPayment, Payment, SETTLEMENT_PAYMENTS_PUZZLE_HASH},
+};
+use chia_sdk_types::TradePrice;
+use indexmap::IndexMap;
+
+use crate::{calculate_nft_royalty, calculate_nft_trace_price, DriverError, NftInfo};
+
+/// An amount of XCH or a CAT on one side of a trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetAmount {
+    /// The asset id of the CAT, or [`None`] for XCH.
+    pub asset_id: Option<Bytes32>,
+    pub amount: u64,
+}
+
+impl AssetAmount {
+    pub fn xch(amount: u64) -> Self {
+        Self {
+            asset_id: None,
+            amount,
+        }
+    }
+
+    pub fn cat(asset_id: Bytes32, amount: u64) -> Self {
+        Self {
+            asset_id: Some(asset_id),
+            amount,
+        }
+    }
+
+    /// The puzzle hash of the settlement payments puzzle for this asset,
+    /// which is wrapped in the CAT layer for CATs.
+    pub fn settlement_puzzle_hash(&self) -> Bytes32 {
+        settlement_puzzle_hash(self.asset_id)
+    }
+}
+
+/// The royalty terms of an NFT in a trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NftRoyaltyInfo {
+    pub launcher_id: Bytes32,
+    pub royalty_puzzle_hash: Bytes32,
+    pub royalty_ten_thousandths: u16,
+}
+
+impl NftRoyaltyInfo {
+    pub fn new(
+        launcher_id: Bytes32,
+        royalty_puzzle_hash: Bytes32,
+        royalty_ten_thousandths: u16,
+    ) -> Self {
+        Self {
+            launcher_id,
+            royalty_puzzle_hash,
+            royalty_ten_thousandths,
+        }
+    }
+
+    pub fn from_nft_info<M>(info: &NftInfo<M>) -> Self {
+        Self::new(
+            info.launcher_id,
+            info.royalty_puzzle_hash,
+            info.royalty_ten_thousandths,
+        )
+    }
+}
+
+/// A royalty payment for an NFT, in one of the assets that it's traded for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoyaltyPayment {
+    /// The asset id of the CAT, or [`None`] for XCH.
+    pub asset_id: Option<Bytes32>,
+    /// The payment to the royalty puzzle hash, which uses the NFT's launcher id as the nonce.
+    pub notarized_payment: NotarizedPayment,
+}
+
+impl RoyaltyPayment {
+    /// The puzzle hash of the settlement payments puzzle that must make the payment.
+    pub fn settlement_puzzle_hash(&self) -> Bytes32 {
+        settlement_puzzle_hash(self.asset_id)
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.notarized_payment
+            .payments
+            .iter()
+            .map(|payment| payment.amount)
+            .sum()
+    }
+}
+
+/// The trade prices and royalty payments of an NFT that changes hands in a trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftRoyalties {
+    pub launcher_id: Bytes32,
+    /// The trade prices, which must be used in the [`TransferNft`](chia_sdk_types::TransferNft)
+    /// condition when the NFT is locked into the settlement payments puzzle.
+    pub trade_prices: Vec<TradePrice>,
+    /// The royalty payments asserted by the NFT, one for each trade price.
+    pub payments: Vec<RoyaltyPayment>,
+}
+
+/// The royalties of every NFT in a trade.
+///
+/// NFTs are priced by evenly splitting the fungible assets on the other side of the trade.
+/// Royalties for the offered NFTs are paid by the taker, in addition to the requested assets.
+/// Royalties for the requested NFTs are paid by the maker, in addition to the offered assets.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TradeRoyalties {
+    /// The royalties of the offered NFTs, paid in the requested assets.
+    pub offered: Vec<NftRoyalties>,
+    /// The royalties of the requested NFTs, paid in the offered assets.
+    pub requested: Vec<NftRoyalties>,
+}
+
+impl TradeRoyalties {
+    pub fn calculate(
+        offered_nfts: &[NftRoyaltyInfo],
+        offered_assets: &[AssetAmount],
+        requested_nfts: &[NftRoyaltyInfo],
+        requested_assets: &[AssetAmount],
+    ) -> Result<Self, DriverError> {
+        Ok(Self {
+            offered: nft_royalties(offered_nfts, requested_assets)?,
+            requested: nft_royalties(requested_nfts, offered_assets)?,
+        })
+    }
+
+    /// The total royalties of the offered NFTs for each requested asset.
+    pub fn offered_amounts(&self) -> IndexMap<Option<Bytes32>, u64> {
+        royalty_amounts(&self.offered)
+    }
+
+    /// The total royalties of the requested NFTs for each offered asset.
+    pub fn requested_amounts(&self) -> IndexMap<Option<Bytes32>, u64> {
+        royalty_amounts(&self.requested)
+    }
+
+    /// The royalty payments of the requested NFTs in an offered asset. These must be
+    /// included alongside the taker's payments when spending the maker's settlement coins.
+    pub fn requested_payments(&self, asset_id: Option<Bytes32>) -> Vec<NotarizedPayment> {
+        self.requested
+            .iter()
+            .flat_map(|royalties| &royalties.payments)
+            .filter(|payment| payment.asset_id == asset_id)
+            .map(|payment| payment.notarized_payment.clone())
+            .collect()
+    }
+}
+
+fn settlement_puzzle_hash(asset_id: Option<Bytes32>) -> Bytes32 {
+    match asset_id {
+        Some(asset_id) => {
+            CatArgs::curry_tree_hash(asset_id, SETTLEMENT_PAYMENTS_PUZZLE_HASH.into()).into()
+        }
+        None => SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+    }
+}
+
+fn nft_royalties(
+    nfts: &[NftRoyaltyInfo],
+    assets: &[AssetAmount],
+) -> Result<Vec<NftRoyalties>, DriverError> {
+    let mut result = Vec::with_capacity(nfts.len());
+
+    for nft in nfts {
+        let mut trade_prices = Vec::new();
+        let mut payments = Vec::new();
+
+        // NFTs without royalties don't need to assert any payments.
+        if nft.royalty_ten_thousandths > 0 {
+            for asset in assets {
+                let trade_price = calculate_nft_trace_price(asset.amount, nfts.len())
+                    .ok_or(DriverError::RoyaltyOverflow)?;
+                let royalty = calculate_nft_royalty(trade_price, nft.royalty_ten_thousandths)
+                    .ok_or(DriverError::RoyaltyOverflow)?;
+
+                trade_prices.push(TradePrice {
+                    amount: trade_price,
+                    puzzle_hash: asset.settlement_puzzle_hash(),
+                });
+
+                payments.push(RoyaltyPayment {
+                    asset_id: asset.asset_id,
+                    notarized_payment: NotarizedPayment {
+                        nonce: nft.launcher_id,
+                        payments: vec![Payment::with_memos(
+                            nft.royalty_puzzle_hash,
+                            royalty,
+                            vec![nft.royalty_puzzle_hash.into()],
+                        )],
+                    },
+                });
+            }
+        }
+
+        result.push(NftRoyalties {
+            launcher_id: nft.launcher_id,
+            trade_prices,
+            payments,
+        });
+    }
+
+    Ok(result)
+}
+
+fn royalty_amounts(nfts: &[NftRoyalties]) -> IndexMap<Option<Bytes32>, u64> {
+    let mut amounts = IndexMap::new();
+
+    for payment in nfts.iter().flat_map(|royalties| &royalties.payments) {
+        *amounts.entry(payment.asset_id).or_insert(0) += payment.amount();
+    }
+
+    amounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_royalties() -> anyhow::Result<()> {
+        let asset_id = Bytes32::new([1; 32]);
+        let royalty_puzzle_hash = Bytes32::new([2; 32]);
+
+        let offered_nfts = [
+            NftRoyaltyInfo::new(Bytes32::new([3; 32]), royalty_puzzle_hash, 300),
+            NftRoyaltyInfo::new(Bytes32::new([4; 32]), royalty_puzzle_hash, 0),
+        ];
+        let requested_nfts = [NftRoyaltyInfo::new(
+            Bytes32::new([5; 32]),
+            royalty_puzzle_hash,
+            1000,
+        )];
+
+        let royalties = TradeRoyalties::calculate(
+            &offered_nfts,
+            &[AssetAmount::xch(1001)],
+            &requested_nfts,
+            &[AssetAmount::xch(2000), AssetAmount::cat(asset_id, 500)],
+        )?;
+
+        // The first offered NFT is priced at half of each requested asset.
+        assert_eq!(
+            royalties.offered[0].trade_prices,
+            [
+                TradePrice {
+                    amount: 1000,
+                    puzzle_hash: SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+                },
+                TradePrice {
+                    amount: 250,
+                    puzzle_hash: AssetAmount::cat(asset_id, 0).settlement_puzzle_hash(),
+                }
+            ]
+        );
+        assert_eq!(
+            royalties.offered[0]
+                .payments
+                .iter()
+                .map(RoyaltyPayment::amount)
+                .collect::<Vec<_>>(),
+            [30, 7]
+        );
+
+        // NFTs without royalties have no trade prices.
+        assert!(royalties.offered[1].trade_prices.is_empty());
+        assert!(royalties.offered[1].payments.is_empty());
+
+        assert_eq!(
+            royalties.offered_amounts(),
+            IndexMap::from([(None, 30), (Some(asset_id), 7)])
+        );
+
+        // The requested NFT is priced at all of the offered asset.
+        assert_eq!(royalties.requested[0].trade_prices[0].amount, 1001);
+        assert_eq!(royalties.requested_amounts(), IndexMap::from([(None, 100)]));
+        assert!(royalties.requested_payments(Some(asset_id)).is_empty());
+        assert_eq!(
+            royalties.requested_payments(None),
+            [NotarizedPayment {
+                nonce: Bytes32::new([5; 32]),
+                payments: vec![Payment::with_memos(
+                    royalty_puzzle_hash,
+                    100,
+                    vec![royalty_puzzle_hash.into()]
+                )],
+            }]
+        );
+
+        Ok(())
+    }
+}
@@ -1,2 +1,4 @@
+mod cat_for_nft;
+mod nft_bundle_for_xch;
 mod nft_for_nft;
 mod nft_for_xch;
@@ -0,0 +1,165 @@
+use chia_protocol::SpendBundle;
+use chia_puzzles::{
+    nft::NftMetadata,
+    offer::{
+        NotarizedPayment, Payment, SettlementPaymentsSolution, SETTLEMENT_PAYMENTS_PUZZLE_HASH,
+    },
+};
+use chia_sdk_test::{sign_transaction, Simulator};
+use chia_sdk_types::Conditions;
+
+use crate::{
+    payment_assertion, AssetAmount, Cat, CatSpend, Launcher, Layer, NftMint, NftRoyaltyInfo, Offer,
+    OfferBuilder, SettlementLayer, SpendContext, SpendWithConditions, StandardLayer,
+    TradeRoyalties,
+};
+
+#[test]
+fn test_cat_for_nft() -> anyhow::Result<()> {
+    let mut sim = Simulator::new();
+    let mut ctx = SpendContext::new();
+
+    let (alice_secret_key, alice_pk, alice_puzzle_hash, alice_coin) = sim.new_p2(1000)?;
+    let (bob_secret_key, bob_pk, bob_puzzle_hash, bob_coin) = sim.new_p2(1)?;
+    let (_, _, royalty_puzzle_hash, _) = sim.new_p2(0)?;
+    let alice = StandardLayer::new(alice_pk);
+    let bob = StandardLayer::new(bob_pk);
+
+    // Issue a CAT on the maker side
+    let memos = ctx.hint(alice_puzzle_hash)?;
+    let (issue_cat, cat) = Cat::single_issuance_eve(
+        &mut ctx,
+        alice_coin.coin_id(),
+        1000,
+        Conditions::new().create_coin(alice_puzzle_hash, 1000, Some(memos)),
+    )?;
+    alice.spend(&mut ctx, alice_coin, issue_cat)?;
+    let alice_cat = cat.wrapped_child(alice_puzzle_hash, 1000);
+
+    // Mint an NFT with a 10% royalty on the taker side
+    let (conditions, nft) = Launcher::new(bob_coin.coin_id(), 1).mint_nft(
+        &mut ctx,
+        NftMint::new(NftMetadata::default(), bob_puzzle_hash, 1000, None)
+            .with_royalty_puzzle_hash(royalty_puzzle_hash),
+    )?;
+    bob.spend(&mut ctx, bob_coin, conditions)?;
+
+    sim.spend_coins(
+        ctx.take(),
+        &[alice_secret_key.clone(), bob_secret_key.clone()],
+    )?;
+
+    // Create offer
+    let price = 500;
+    let asset_id = alice_cat.asset_id;
+
+    let royalties = TradeRoyalties::calculate(
+        &[],
+        &[AssetAmount::cat(asset_id, price)],
+        &[NftRoyaltyInfo::from_nft_info(&nft.info)],
+        &[],
+    )?;
+
+    let royalty_amount = royalties.requested_amounts()[&Some(asset_id)];
+    assert_eq!(royalty_amount, 50);
+
+    let settlement = ctx.settlement_payments_puzzle()?;
+    let nonce = Offer::nonce(vec![alice_cat.coin.coin_id()]);
+
+    let nft_puzzle = nft
+        .info
+        .clone()
+        .into_layers(settlement)
+        .construct_puzzle(&mut ctx)?;
+
+    let (assertions, builder) = OfferBuilder::new(nonce)
+        .request(
+            &mut ctx,
+            &nft_puzzle,
+            vec![Payment::with_memos(
+                alice_puzzle_hash,
+                1,
+                vec![alice_puzzle_hash.into()],
+            )],
+        )?
+        .finish();
+
+    // The maker offers the price along with the royalty
+    let offered_amount = price + royalty_amount;
+    let memos = ctx.hint(alice_puzzle_hash)?;
+
+    let inner_spend = alice.spend_with_conditions(
+        &mut ctx,
+        Conditions::new()
+            .create_coin(SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(), offered_amount, None)
+            .create_coin(alice_puzzle_hash, 1000 - offered_amount, Some(memos))
+            .extend(assertions),
+    )?;
+    Cat::spend_all(&mut ctx, &[CatSpend::new(alice_cat, inner_spend)])?;
+
+    let settlement_cat =
+        alice_cat.wrapped_child(SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(), offered_amount);
+    assert_eq!(
+        settlement_cat.coin.puzzle_hash,
+        AssetAmount::cat(asset_id, price).settlement_puzzle_hash()
+    );
+
+    let coin_spends = ctx.take();
+    let signature = sign_transaction(&coin_spends, &[alice_secret_key])?;
+
+    // Fulfill offer
+    let mut builder = builder.take(SpendBundle::new(coin_spends, signature));
+
+    let (fulfill_puzzle, payments) = builder.fulfill().expect("cannot fulfill offer");
+    assert_eq!(
+        fulfill_puzzle.curried_puzzle_hash(),
+        ctx.tree_hash(nft_puzzle)
+    );
+
+    let receive_nonce = Offer::nonce(vec![nft.coin.coin_id()]);
+    let receive_payment = NotarizedPayment {
+        nonce: receive_nonce,
+        payments: vec![Payment::with_memos(
+            bob_puzzle_hash,
+            price,
+            vec![bob_puzzle_hash.into()],
+        )],
+    };
+
+    // The NFT asserts that the royalty is paid out of the maker's settlement coin
+    let settlement_nft = nft.lock_settlement(
+        &mut ctx,
+        &bob,
+        royalties.requested[0].trade_prices.clone(),
+        Conditions::new().with(payment_assertion(
+            settlement_cat.coin.puzzle_hash,
+            &receive_payment,
+        )),
+    )?;
+
+    let swapped_nft = settlement_nft.unlock_settlement(&mut ctx, payments)?;
+
+    let mut notarized_payments = vec![receive_payment];
+    notarized_payments.extend(royalties.requested_payments(Some(asset_id)));
+
+    let inner_spend = SettlementLayer
+        .construct_spend(&mut ctx, SettlementPaymentsSolution { notarized_payments })?;
+    Cat::spend_all(&mut ctx, &[CatSpend::new(settlement_cat, inner_spend)])?;
+
+    let coin_spends = ctx.take();
+    let signature = sign_transaction(&coin_spends, &[bob_secret_key])?;
+
+    let spend_bundle = builder.bundle(SpendBundle::new(coin_spends, signature));
+    sim.new_transaction(spend_bundle)?;
+
+    assert_eq!(swapped_nft.info.p2_puzzle_hash, alice_puzzle_hash);
+    assert!(sim.coin_state(swapped_nft.coin.coin_id()).is_some());
+
+    let bob_cat = settlement_cat.wrapped_child(bob_puzzle_hash, price);
+    assert!(sim.coin_state(bob_cat.coin.coin_id()).is_some());
+
+    let royalty_cat = settlement_cat.wrapped_child(royalty_puzzle_hash, royalty_amount);
+    assert!(sim.coin_state(royalty_cat.coin.coin_id()).is_some());
+
+    Ok(())
+}
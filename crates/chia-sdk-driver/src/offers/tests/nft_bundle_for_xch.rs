@@ -0,0 +1,176 @@
+use chia_protocol::{Coin, SpendBundle};
+use chia_puzzles::{
+    nft::NftMetadata,
+    offer::{
+        NotarizedPayment, Payment, SettlementPaymentsSolution, SETTLEMENT_PAYMENTS_PUZZLE_HASH,
+    },
+};
+use chia_sdk_test::{sign_transaction, Simulator};
+use chia_sdk_types::Conditions;
+
+use crate::{
+    payment_assertion, AssetAmount, Launcher, Layer, NftMint, NftRoyaltyInfo, Offer, OfferBuilder,
+    SettlementLayer, SpendContext, StandardLayer, TradeRoyalties,
+};
+
+#[test]
+fn test_nft_bundle_for_xch() -> anyhow::Result<()> {
+    let mut sim = Simulator::new();
+    let mut ctx = SpendContext::new();
+
+    let (alice_secret_key, alice_pk, alice_puzzle_hash, alice_coin) = sim.new_p2(1)?;
+    let (bob_secret_key, bob_pk, bob_puzzle_hash, bob_coin) = sim.new_p2(1_000_000_000_000)?;
+    let (_, _, royalty_puzzle_hash, _) = sim.new_p2(0)?;
+    let alice = StandardLayer::new(alice_pk);
+
+    // Mint two NFTs with different royalties on the maker side
+    let mut nfts = Vec::new();
+
+    for (coin, royalty_ten_thousandths) in
+        [(alice_coin, 300), (sim.new_coin(alice_puzzle_hash, 1), 500)]
+    {
+        let (conditions, nft) = Launcher::new(coin.coin_id(), 1).mint_nft(
+            &mut ctx,
+            NftMint::new(
+                NftMetadata::default(),
+                alice_puzzle_hash,
+                royalty_ten_thousandths,
+                None,
+            )
+            .with_royalty_puzzle_hash(royalty_puzzle_hash),
+        )?;
+        alice.spend(&mut ctx, coin, conditions)?;
+        nfts.push(nft);
+    }
+
+    sim.spend_coins(ctx.take(), &[alice_secret_key.clone()])?;
+
+    // Create offer
+    let price = 500_000_000_000;
+
+    let royalties = TradeRoyalties::calculate(
+        &nfts
+            .iter()
+            .map(|nft| NftRoyaltyInfo::from_nft_info(&nft.info))
+            .collect::<Vec<_>>(),
+        &[],
+        &[],
+        &[AssetAmount::xch(price)],
+    )?;
+
+    let royalty_amount = royalties.offered_amounts()[&None];
+    assert_eq!(royalty_amount, 7_500_000_000 + 12_500_000_000);
+
+    let settlement = ctx.settlement_payments_puzzle()?;
+    let nonce = Offer::nonce(nfts.iter().map(|nft| nft.coin.coin_id()).collect());
+
+    let (assertions, builder) = OfferBuilder::new(nonce)
+        .request(
+            &mut ctx,
+            &settlement,
+            vec![Payment::new(alice_puzzle_hash, price)],
+        )?
+        .request_royalties(&mut ctx, &royalties.offered)?
+        .finish();
+
+    let mut settlement_nfts = Vec::new();
+    let mut assertions = Conditions::new().extend(assertions);
+
+    for (nft, nft_royalties) in nfts.iter().zip(&royalties.offered) {
+        settlement_nfts.push(nft.clone().lock_settlement(
+            &mut ctx,
+            &alice,
+            nft_royalties.trade_prices.clone(),
+            assertions,
+        )?);
+        assertions = Conditions::new();
+    }
+
+    let coin_spends = ctx.take();
+    let signature = sign_transaction(&coin_spends, &[alice_secret_key])?;
+
+    // Fulfill offer
+    let mut builder = builder.take(SpendBundle::new(coin_spends, signature));
+
+    let (fulfill_puzzle, payments) = builder.fulfill().expect("cannot fulfill offer");
+    assert_eq!(
+        fulfill_puzzle.curried_puzzle_hash(),
+        SETTLEMENT_PAYMENTS_PUZZLE_HASH
+    );
+    assert_eq!(payments.len(), 3);
+
+    let receive_nonce = Offer::nonce(vec![bob_coin.coin_id()]);
+    let receive_payment = NotarizedPayment {
+        nonce: receive_nonce,
+        payments: vec![Payment::with_memos(
+            bob_puzzle_hash,
+            1,
+            vec![bob_puzzle_hash.into()],
+        )],
+    };
+
+    let total_amount = price + royalty_amount;
+    let mut bob_conditions =
+        Conditions::new().create_coin(SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(), total_amount, None);
+
+    for nft in &nfts {
+        let nft_puzzle = nft
+            .info
+            .clone()
+            .into_layers(settlement)
+            .construct_puzzle(&mut ctx)?;
+        let hash = ctx.tree_hash(nft_puzzle).into();
+        bob_conditions = bob_conditions.with(payment_assertion(hash, &receive_payment));
+    }
+
+    StandardLayer::new(bob_pk).spend(&mut ctx, bob_coin, bob_conditions)?;
+
+    let settlement_coin = Coin::new(
+        bob_coin.coin_id(),
+        SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+        total_amount,
+    );
+
+    let coin_spend = SettlementLayer.construct_coin_spend(
+        &mut ctx,
+        settlement_coin,
+        SettlementPaymentsSolution {
+            notarized_payments: payments,
+        },
+    )?;
+    ctx.insert(coin_spend);
+
+    let mut swapped_nfts = Vec::new();
+
+    for settlement_nft in settlement_nfts {
+        swapped_nfts
+            .push(settlement_nft.unlock_settlement(&mut ctx, vec![receive_payment.clone()])?);
+    }
+
+    let coin_spends = ctx.take();
+    let signature = sign_transaction(&coin_spends, &[bob_secret_key])?;
+
+    let spend_bundle = builder.bundle(SpendBundle::new(coin_spends, signature));
+
+    sim.new_transaction(spend_bundle)?;
+
+    for nft in swapped_nfts {
+        assert_eq!(nft.info.p2_puzzle_hash, bob_puzzle_hash);
+        assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+    }
+
+    for payment in royalties
+        .offered
+        .iter()
+        .flat_map(|royalty| &royalty.payments)
+    {
+        let royalty_coin = Coin::new(
+            settlement_coin.coin_id(),
+            royalty_puzzle_hash,
+            payment.amount(),
+        );
+        assert!(sim.coin_state(royalty_coin.coin_id()).is_some());
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 mod cat;
+mod cat1;
 mod clawback;
 mod did;
 mod intermediate_launcher;
@@ -6,6 +7,7 @@ mod launcher;
 mod nft;
 
 pub use cat::*;
+pub use cat1::*;
 pub use clawback::*;
 pub use did::*;
 pub use intermediate_launcher::*;
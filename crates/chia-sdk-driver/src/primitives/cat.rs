@@ -4,16 +4,21 @@ use chia_puzzles::{
     cat::{CatArgs, CatSolution, EverythingWithSignatureTailArgs, GenesisByCoinIdTailArgs},
     CoinProof, LineageProof,
 };
-use chia_sdk_types::{run_puzzle, Condition, Conditions, CreateCoin};
+use chia_sdk_types::{
+    run_puzzle, Condition, Conditions, CreateCoin, DelegatedTailArgs, DelegatedTailSolution,
+    GenesisByPuzzleHashTailArgs, GenesisByPuzzleHashTailSolution,
+};
 use clvm_traits::{clvm_quote, FromClvm};
 use clvmr::{Allocator, NodePtr};
 
-use crate::{CatLayer, DriverError, Layer, Puzzle, Spend, SpendContext};
+use crate::{CatLayer, DriverError, Layer, Puzzle, Spend, SpendContext, SpendWithConditions};
 
 mod cat_spend;
+mod cat_tail;
 mod single_cat_spend;
 
 pub use cat_spend::*;
+pub use cat_tail::*;
 pub use single_cat_spend::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -74,6 +79,55 @@ impl Cat {
         )
     }
 
+    /// Issues a CAT which can only be created by coins whose parent has the puzzle hash of `parent_coin`.
+    /// The eve CAT must be created by `parent_coin` itself, since its parent is revealed in the TAIL solution.
+    pub fn genesis_by_puzzle_hash_eve(
+        ctx: &mut SpendContext,
+        parent_coin: Coin,
+        amount: u64,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError> {
+        let tail = ctx.curry(GenesisByPuzzleHashTailArgs::new(parent_coin.puzzle_hash))?;
+        let tail_solution = ctx.alloc(&GenesisByPuzzleHashTailSolution::new(
+            parent_coin.parent_coin_info,
+            parent_coin.amount,
+        ))?;
+
+        Self::create_and_spend_eve(
+            ctx,
+            parent_coin.coin_id(),
+            ctx.tree_hash(tail).into(),
+            amount,
+            extra_conditions.run_cat_tail(tail, tail_solution),
+        )
+    }
+
+    /// Issues a CAT with the delegated TAIL, which requires a signature from `public_key`
+    /// on the tree hash of the delegated puzzle. The delegated puzzle is run with the same
+    /// arguments as the TAIL, so it can output additional conditions or further restrict issuance.
+    pub fn delegated_issuance_eve(
+        ctx: &mut SpendContext,
+        parent_coin_id: Bytes32,
+        public_key: PublicKey,
+        delegated_spend: Spend,
+        amount: u64,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError> {
+        let tail = ctx.curry(DelegatedTailArgs::new(public_key))?;
+        let tail_solution = ctx.alloc(&DelegatedTailSolution::new(
+            delegated_spend.puzzle,
+            delegated_spend.solution,
+        ))?;
+
+        Self::create_and_spend_eve(
+            ctx,
+            parent_coin_id,
+            ctx.tree_hash(tail).into(),
+            amount,
+            extra_conditions.run_cat_tail(tail, tail_solution),
+        )
+    }
+
     /// Creates and spends an eve CAT with the provided conditions.
     /// To issue the CAT, you will need to reveal the TAIL puzzle and solution.
     /// This can be done with the [`RunCatTail`] condition.
@@ -112,6 +166,72 @@ impl Cat {
         ))
     }
 
+    /// Spends one or more CATs in a ring, issuing `amount` more of the CAT.
+    /// The first CAT reveals the TAIL, so its conditions must create `amount` more than it holds.
+    ///
+    /// The new mojos aren't backed by any of the CATs, so the caller must also spend at least
+    /// `amount` of XCH in the same spend bundle without recreating it, or the mint will be rejected.
+    ///
+    /// For the [`EverythingWithSignatureTailArgs`] TAIL, the spend will require a signature
+    /// from the issuer's key, which can be found with `RequiredSignature::from_coin_spend`.
+    pub fn issue_more<I>(
+        ctx: &mut SpendContext,
+        tail: Spend,
+        amount: u64,
+        cat: Cat,
+        p2: &I,
+        conditions: Conditions,
+        other_spends: &[CatSpend],
+    ) -> Result<(), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let extra_delta = i64::try_from(amount)?;
+        Self::spend_with_tail(ctx, tail, extra_delta, cat, p2, conditions, other_spends)
+    }
+
+    /// Spends one or more CATs in a ring, melting `amount` of the CAT.
+    /// The first CAT reveals the TAIL, so its conditions must create `amount` less than it holds.
+    ///
+    /// For the [`EverythingWithSignatureTailArgs`] TAIL, the spend will require a signature
+    /// from the issuer's key, which can be found with `RequiredSignature::from_coin_spend`.
+    pub fn melt<I>(
+        ctx: &mut SpendContext,
+        tail: Spend,
+        amount: u64,
+        cat: Cat,
+        p2: &I,
+        conditions: Conditions,
+        other_spends: &[CatSpend],
+    ) -> Result<(), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let extra_delta = -i64::try_from(amount)?;
+        Self::spend_with_tail(ctx, tail, extra_delta, cat, p2, conditions, other_spends)
+    }
+
+    fn spend_with_tail<I>(
+        ctx: &mut SpendContext,
+        tail: Spend,
+        extra_delta: i64,
+        cat: Cat,
+        p2: &I,
+        conditions: Conditions,
+        other_spends: &[CatSpend],
+    ) -> Result<(), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let inner_spend =
+            p2.spend_with_conditions(ctx, conditions.run_cat_tail(tail.puzzle, tail.solution))?;
+
+        let mut cat_spends = vec![CatSpend::with_extra_delta(cat, inner_spend, extra_delta)];
+        cat_spends.extend_from_slice(other_spends);
+
+        Self::spend_all(ctx, &cat_spends)
+    }
+
     /// Creates coin spends for one or more CATs in a ring.
     /// Without the ring announcements, CAT spends cannot share inputs and outputs.
     ///
@@ -137,7 +257,7 @@ impl Cat {
                 .filter_map(|ptr| ctx.extract::<CreateCoin<NodePtr>>(ptr).ok());
 
             let delta = create_coins.fold(
-                i128::from(cat.coin.amount) - i128::from(*extra_delta),
+                i128::from(cat.coin.amount) + i128::from(*extra_delta),
                 |delta, create_coin| delta - i128::from(create_coin.amount),
             );
 
@@ -211,6 +331,28 @@ impl Cat {
 }
 
 impl Cat {
+    /// Parses the TAIL revealed by a CAT spend, such as the eve spend that issued it.
+    /// Returns [`None`] if the spend didn't reveal a TAIL, or if it's not one of the known TAILs.
+    pub fn parse_tail(
+        allocator: &mut Allocator,
+        puzzle: Puzzle,
+        solution: NodePtr,
+    ) -> Result<Option<CatTail>, DriverError> {
+        let Some(layer) = CatLayer::<Puzzle>::parse_puzzle(allocator, puzzle)? else {
+            return Ok(None);
+        };
+        let solution = CatLayer::<Puzzle>::parse_solution(allocator, solution)?;
+
+        let output = run_puzzle(
+            allocator,
+            layer.inner_puzzle.ptr(),
+            solution.inner_puzzle_solution,
+        )?;
+        let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+        CatTail::parse_conditions(allocator, &conditions)
+    }
+
     pub fn parse_children(
         allocator: &mut Allocator,
         parent_coin: Coin,
@@ -270,6 +412,7 @@ mod tests {
     use chia_consensus::gen::validation_error::ErrorCode;
     use chia_puzzles::cat::EverythingWithSignatureTailArgs;
     use chia_sdk_test::{Simulator, SimulatorError};
+    use clvm_traits::ToClvm;
     use rstest::rstest;
 
     use crate::{SpendWithConditions, StandardLayer};
@@ -569,4 +712,205 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_cat_issue_more() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let memos = ctx.hint(puzzle_hash)?;
+        let conditions = Conditions::new()
+            .create_coin(puzzle_hash, 600, Some(memos))
+            .create_coin(puzzle_hash, 400, Some(memos));
+        let (issue_cat, cat) = Cat::multi_issuance_eve(ctx, coin.coin_id(), pk, 1000, conditions)?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let first = cat.wrapped_child(puzzle_hash, 600);
+        let second = cat.wrapped_child(puzzle_hash, 400);
+
+        // The issued mojos are backed by an XCH coin, which isn't recreated.
+        let funding = sim.new_coin(puzzle_hash, 500);
+        p2.spend(ctx, funding, Conditions::new())?;
+
+        let tail = ctx.curry(EverythingWithSignatureTailArgs::new(pk))?;
+        let other_spend = CatSpend::new(
+            second,
+            p2.spend_with_conditions(
+                ctx,
+                Conditions::new().create_coin(puzzle_hash, 400, Some(memos)),
+            )?,
+        );
+
+        Cat::issue_more(
+            ctx,
+            Spend::new(tail, NodePtr::NIL),
+            500,
+            first,
+            &p2,
+            Conditions::new().create_coin(puzzle_hash, 1100, Some(memos)),
+            &[other_spend],
+        )?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let issued = first.wrapped_child(puzzle_hash, 1100);
+        assert!(sim.coin_state(issued.coin.coin_id()).is_some());
+        assert!(sim
+            .coin_state(second.wrapped_child(puzzle_hash, 400).coin.coin_id())
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cat_melt_helper() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        // The TAIL is controlled by a separate issuer key.
+        let issuer = chia_bls::SecretKey::from_seed(&[42; 32]);
+        let issuer_pk = issuer.public_key();
+
+        let memos = ctx.hint(puzzle_hash)?;
+        let conditions = Conditions::new()
+            .create_coin(puzzle_hash, 600, Some(memos))
+            .create_coin(puzzle_hash, 400, Some(memos));
+        let (issue_cat, cat) =
+            Cat::multi_issuance_eve(ctx, coin.coin_id(), issuer_pk, 1000, conditions)?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone(), issuer.clone()])?;
+
+        let first = cat.wrapped_child(puzzle_hash, 600);
+        let second = cat.wrapped_child(puzzle_hash, 400);
+
+        // The second CAT's value is moved into the first, and 250 is melted in total.
+        let tail = ctx.curry(EverythingWithSignatureTailArgs::new(issuer_pk))?;
+        let other_spend = CatSpend::new(second, p2.spend_with_conditions(ctx, Conditions::new())?);
+
+        Cat::melt(
+            ctx,
+            Spend::new(tail, NodePtr::NIL),
+            250,
+            first,
+            &p2,
+            Conditions::new().create_coin(puzzle_hash, 750, Some(memos)),
+            &[other_spend],
+        )?;
+
+        // The TAIL requires a signature from the issuer.
+        let coin_spends = ctx.take();
+        assert!(matches!(
+            sim.spend_coins(coin_spends.clone(), &[sk.clone()])
+                .unwrap_err(),
+            SimulatorError::MissingKey
+        ));
+        sim.spend_coins(coin_spends, &[sk, issuer])?;
+
+        let remaining = first.wrapped_child(puzzle_hash, 750);
+        assert!(sim.coin_state(remaining.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    fn parse_eve_tail(
+        sim: &Simulator,
+        ctx: &mut SpendContext,
+        eve: Cat,
+    ) -> anyhow::Result<CatTail> {
+        let puzzle = sim
+            .puzzle_reveal(eve.coin.coin_id())
+            .expect("missing puzzle");
+        let solution = sim.solution(eve.coin.coin_id()).expect("missing solution");
+
+        let puzzle = puzzle.to_clvm(&mut ctx.allocator)?;
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle);
+        let solution = solution.to_clvm(&mut ctx.allocator)?;
+
+        Ok(Cat::parse_tail(&mut ctx.allocator, puzzle, solution)?.expect("missing tail"))
+    }
+
+    #[test]
+    fn test_genesis_by_puzzle_hash_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let memos = ctx.hint(puzzle_hash)?;
+        let (issue_cat, cat) = Cat::genesis_by_puzzle_hash_eve(
+            ctx,
+            coin,
+            1,
+            Conditions::new().create_coin(puzzle_hash, 1, Some(memos)),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let tail = parse_eve_tail(&sim, ctx, cat)?;
+        assert_eq!(
+            tail,
+            CatTail::GenesisByPuzzleHash(GenesisByPuzzleHashTailArgs::new(puzzle_hash))
+        );
+        assert_eq!(tail.asset_id(), cat.asset_id);
+
+        let cat = cat.wrapped_child(puzzle_hash, 1);
+        assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delegated_issuance_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        // The delegated puzzle doesn't add any conditions, so the signature alone approves issuance.
+        let delegated_puzzle = ctx.alloc(&clvm_quote!(()))?;
+        let delegated_spend = Spend::new(delegated_puzzle, NodePtr::NIL);
+
+        let memos = ctx.hint(puzzle_hash)?;
+        let (issue_cat, cat) = Cat::delegated_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            pk,
+            delegated_spend,
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, Some(memos)),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let tail = parse_eve_tail(&sim, ctx, cat)?;
+        assert_eq!(tail, CatTail::Delegated(DelegatedTailArgs::new(pk)));
+        assert_eq!(tail.asset_id(), cat.asset_id);
+
+        // The same delegated puzzle can be used to melt some of the supply.
+        let tail = ctx.curry(DelegatedTailArgs::new(pk))?;
+        let tail_solution =
+            ctx.alloc(&DelegatedTailSolution::new(delegated_puzzle, NodePtr::NIL))?;
+
+        Cat::melt(
+            ctx,
+            Spend::new(tail, tail_solution),
+            400,
+            cat.wrapped_child(puzzle_hash, 1000),
+            &p2,
+            Conditions::new().create_coin(puzzle_hash, 600, Some(memos)),
+            &[],
+        )?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let cat = cat
+            .wrapped_child(puzzle_hash, 1000)
+            .wrapped_child(puzzle_hash, 600);
+        assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,79 @@
+use chia_protocol::Bytes32;
+use chia_puzzles::cat::{
+    EverythingWithSignatureTailArgs, GenesisByCoinIdTailArgs,
+    EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE_HASH, GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
+};
+use chia_sdk_types::{
+    Condition, DelegatedTailArgs, GenesisByPuzzleHashTailArgs, Mod, DELEGATED_TAIL_PUZZLE_HASH,
+    GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH,
+};
+use clvm_traits::FromClvm;
+use clvmr::{Allocator, NodePtr};
+
+use crate::{DriverError, Puzzle};
+
+/// One of the known TAIL programs, which determines how a CAT can be issued or melted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatTail {
+    /// Only allows a single issuance, from a specific genesis coin.
+    GenesisByCoinId(GenesisByCoinIdTailArgs),
+    /// Only allows issuance from coins whose parent has a specific puzzle hash.
+    GenesisByPuzzleHash(GenesisByPuzzleHashTailArgs),
+    /// Allows any issuance or melting signed by a public key.
+    EverythingWithSignature(EverythingWithSignatureTailArgs),
+    /// Allows issuance or melting with a delegated puzzle signed by a public key.
+    Delegated(DelegatedTailArgs),
+}
+
+impl CatTail {
+    /// Parses a TAIL program, returning [`None`] if it's not one of the known TAILs.
+    pub fn parse(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        let tail = if puzzle.mod_hash == GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH {
+            Self::GenesisByCoinId(GenesisByCoinIdTailArgs::from_clvm(allocator, puzzle.args)?)
+        } else if puzzle.mod_hash == GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH {
+            Self::GenesisByPuzzleHash(GenesisByPuzzleHashTailArgs::from_clvm(
+                allocator,
+                puzzle.args,
+            )?)
+        } else if puzzle.mod_hash == EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE_HASH {
+            Self::EverythingWithSignature(EverythingWithSignatureTailArgs::from_clvm(
+                allocator,
+                puzzle.args,
+            )?)
+        } else if puzzle.mod_hash == DELEGATED_TAIL_PUZZLE_HASH {
+            Self::Delegated(DelegatedTailArgs::from_clvm(allocator, puzzle.args)?)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(tail))
+    }
+
+    /// Finds the TAIL revealed by a [`RunCatTail`](chia_sdk_types::RunCatTail) condition, if there is one.
+    /// Returns [`None`] if no TAIL was revealed, or if it's not one of the known TAILs.
+    pub fn parse_conditions(
+        allocator: &Allocator,
+        conditions: &[Condition<NodePtr>],
+    ) -> Result<Option<Self>, DriverError> {
+        let Some(run_cat_tail) = conditions.iter().find_map(Condition::as_run_cat_tail) else {
+            return Ok(None);
+        };
+
+        Self::parse(allocator, Puzzle::parse(allocator, run_cat_tail.program))
+    }
+
+    /// The asset id of CATs issued with this TAIL, which is its tree hash.
+    pub fn asset_id(&self) -> Bytes32 {
+        match self {
+            Self::GenesisByCoinId(args) => args.curry_tree_hash(),
+            Self::GenesisByPuzzleHash(args) => args.curry_tree_hash(),
+            Self::EverythingWithSignature(args) => args.curry_tree_hash(),
+            Self::Delegated(args) => args.curry_tree_hash(),
+        }
+        .into()
+    }
+}
@@ -0,0 +1,334 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{cat::CatSolution, CoinProof, LineageProof};
+use chia_sdk_types::{run_puzzle, Condition, Conditions};
+use clvm_traits::FromClvm;
+use clvmr::{Allocator, NodePtr};
+
+use crate::{
+    cat1_puzzle_hash, Cat, Cat1Layer, DriverError, Layer, Puzzle, Spend, SpendContext,
+    SpendWithConditions,
+};
+
+/// The mode of the message sent from a melted CAT1 to the eve CAT2 that replaces it.
+/// Both the sender and the receiver are committed to by coin id.
+const MIGRATION_MESSAGE_MODE: u8 = 0b111_111;
+
+/// A coin wrapped in the legacy CAT1 puzzle.
+///
+/// CAT1 is no longer supported by wallets, so the only thing that can be done with these coins
+/// is melting them (which requires the TAIL to allow it), usually to reissue them as CAT2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cat1 {
+    pub coin: Coin,
+    pub lineage_proof: Option<LineageProof>,
+    pub asset_id: Bytes32,
+    pub p2_puzzle_hash: Bytes32,
+}
+
+impl Cat1 {
+    pub fn new(
+        coin: Coin,
+        lineage_proof: Option<LineageProof>,
+        asset_id: Bytes32,
+        p2_puzzle_hash: Bytes32,
+    ) -> Self {
+        Self {
+            coin,
+            lineage_proof,
+            asset_id,
+            p2_puzzle_hash,
+        }
+    }
+
+    /// Melts the entire value of this CAT1 by revealing its TAIL.
+    /// Any other conditions are output by the inner puzzle, but cannot create coins.
+    pub fn melt<I>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &I,
+        tail: Spend,
+        extra_conditions: Conditions,
+    ) -> Result<(), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let inner_spend = p2.spend_with_conditions(
+            ctx,
+            extra_conditions.run_cat_tail(tail.puzzle, tail.solution),
+        )?;
+
+        let layer = Cat1Layer::new(self.asset_id, inner_spend.puzzle);
+        let puzzle = layer.construct_puzzle(ctx)?;
+        let solution = layer.construct_solution(
+            ctx,
+            CatSolution {
+                lineage_proof: self.lineage_proof,
+                prev_coin_id: self.coin.coin_id(),
+                this_coin_info: self.coin,
+                next_coin_proof: CoinProof {
+                    parent_coin_info: self.coin.parent_coin_info,
+                    inner_puzzle_hash: self.p2_puzzle_hash,
+                    amount: self.coin.amount,
+                },
+                prev_subtotal: 0,
+                extra_delta: -i64::try_from(self.coin.amount)?,
+                inner_puzzle_solution: inner_spend.solution,
+            },
+        )?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))
+    }
+
+    /// Melts this CAT1 and reissues the same amount as an eve CAT2, using a TAIL that
+    /// allows issuance from a snapshot of CAT1 balances (such as [`DelegatedTailArgs`]).
+    ///
+    /// Since CAT1 coins can't create unwrapped coins, the eve CAT2 must be created by
+    /// a separate parent coin. The returned conditions must be output by that parent.
+    ///
+    /// The melt sends a message to the eve CAT2, which must receive it. Since every message
+    /// must be both sent and received, neither spend can be included in a block without the other.
+    /// Announcements aren't used, since the CAT1 puzzle doesn't output them unchanged.
+    ///
+    /// [`DelegatedTailArgs`]: chia_sdk_types::DelegatedTailArgs
+    pub fn migrate<I>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &I,
+        cat1_tail: Spend,
+        parent_coin_id: Bytes32,
+        cat2_tail: Spend,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let message = self.asset_id.to_vec();
+        let sender = vec![ctx.alloc(&self.coin.coin_id())?];
+
+        let (parent_conditions, eve) = Cat::create_and_spend_eve(
+            ctx,
+            parent_coin_id,
+            ctx.tree_hash(cat2_tail.puzzle).into(),
+            self.coin.amount,
+            extra_conditions
+                .receive_message(MIGRATION_MESSAGE_MODE, message.clone().into(), sender)
+                .run_cat_tail(cat2_tail.puzzle, cat2_tail.solution),
+        )?;
+
+        let receiver = vec![ctx.alloc(&eve.coin.coin_id())?];
+
+        self.melt(
+            ctx,
+            p2,
+            cat1_tail,
+            Conditions::new().send_message(MIGRATION_MESSAGE_MODE, message.into(), receiver),
+        )?;
+
+        Ok((parent_conditions, eve))
+    }
+
+    /// Returns the lineage proof that would be used by each child.
+    pub fn child_lineage_proof(&self) -> LineageProof {
+        LineageProof {
+            parent_parent_coin_info: self.coin.parent_coin_info,
+            parent_inner_puzzle_hash: self.p2_puzzle_hash,
+            parent_amount: self.coin.amount,
+        }
+    }
+
+    /// Creates a wrapped CAT1 for a given output.
+    #[must_use]
+    pub fn wrapped_child(&self, p2_puzzle_hash: Bytes32, amount: u64) -> Self {
+        let puzzle_hash = cat1_puzzle_hash(self.asset_id, p2_puzzle_hash.into());
+        Self {
+            coin: Coin::new(self.coin.coin_id(), puzzle_hash.into(), amount),
+            lineage_proof: Some(self.child_lineage_proof()),
+            asset_id: self.asset_id,
+            p2_puzzle_hash,
+        }
+    }
+}
+
+impl Cat1 {
+    pub fn parse_children(
+        allocator: &mut Allocator,
+        parent_coin: Coin,
+        parent_puzzle: Puzzle,
+        parent_solution: NodePtr,
+    ) -> Result<Option<Vec<Self>>, DriverError>
+    where
+        Self: Sized,
+    {
+        let Some(parent_layer) = Cat1Layer::<Puzzle>::parse_puzzle(allocator, parent_puzzle)?
+        else {
+            return Ok(None);
+        };
+        let parent_solution = Cat1Layer::<Puzzle>::parse_solution(allocator, parent_solution)?;
+
+        let output = run_puzzle(
+            allocator,
+            parent_layer.inner_puzzle.ptr(),
+            parent_solution.inner_puzzle_solution,
+        )?;
+        let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+        let parent = Self::new(
+            parent_coin,
+            parent_solution.lineage_proof,
+            parent_layer.asset_id,
+            parent_layer.inner_puzzle.curried_puzzle_hash().into(),
+        );
+
+        let outputs = conditions
+            .into_iter()
+            .filter_map(Condition::into_create_coin)
+            .map(|create_coin| parent.wrapped_child(create_coin.puzzle_hash, create_coin.amount))
+            .collect();
+
+        Ok(Some(outputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_consensus::gen::validation_error::ErrorCode;
+    use chia_puzzles::cat::EverythingWithSignatureTailArgs;
+    use chia_sdk_test::{Simulator, SimulatorError};
+    use clvm_traits::{clvm_quote, ToClvm};
+
+    use crate::StandardLayer;
+
+    use super::*;
+
+    /// Issues a CAT1 by hand, since new CAT1 tokens are no longer supported.
+    fn issue_cat1(
+        ctx: &mut SpendContext,
+        parent_coin_id: Bytes32,
+        tail: NodePtr,
+        amount: u64,
+        conditions: Conditions,
+    ) -> anyhow::Result<(Conditions, Cat1)> {
+        let asset_id = ctx.tree_hash(tail).into();
+        let inner_puzzle = ctx.alloc(&clvm_quote!(conditions.run_cat_tail(tail, NodePtr::NIL)))?;
+        let inner_puzzle_hash = ctx.tree_hash(inner_puzzle).into();
+        let layer = Cat1Layer::new(asset_id, inner_puzzle);
+        let puzzle = layer.construct_puzzle(ctx)?;
+
+        let eve = Cat1::new(
+            Coin::new(parent_coin_id, ctx.tree_hash(puzzle).into(), amount),
+            None,
+            asset_id,
+            inner_puzzle_hash,
+        );
+
+        let solution = layer.construct_solution(
+            ctx,
+            CatSolution {
+                lineage_proof: None,
+                prev_coin_id: eve.coin.coin_id(),
+                this_coin_info: eve.coin,
+                next_coin_proof: CoinProof {
+                    parent_coin_info: parent_coin_id,
+                    inner_puzzle_hash,
+                    amount,
+                },
+                prev_subtotal: 0,
+                extra_delta: 0,
+                inner_puzzle_solution: NodePtr::NIL,
+            },
+        )?;
+        ctx.spend(eve.coin, Spend::new(puzzle, solution))?;
+
+        Ok((
+            Conditions::new().create_coin(eve.coin.puzzle_hash, amount, None),
+            eve,
+        ))
+    }
+
+    #[test]
+    fn test_cat1_migration() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let tail = ctx.curry(EverythingWithSignatureTailArgs::new(pk))?;
+        let (issue_cat1, eve) = issue_cat1(
+            ctx,
+            coin.coin_id(),
+            tail,
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, None),
+        )?;
+        p2.spend(ctx, coin, issue_cat1)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // Find the CAT1 from its parent spend, like a wallet would.
+        let parent_puzzle = sim
+            .puzzle_reveal(eve.coin.coin_id())
+            .expect("missing puzzle");
+        let parent_solution = sim.solution(eve.coin.coin_id()).expect("missing solution");
+        let parent_puzzle = parent_puzzle.to_clvm(&mut ctx.allocator)?;
+        let parent_puzzle = Puzzle::parse(&ctx.allocator, parent_puzzle);
+        let parent_solution = parent_solution.to_clvm(&mut ctx.allocator)?;
+
+        let cat1 =
+            Cat1::parse_children(&mut ctx.allocator, eve.coin, parent_puzzle, parent_solution)?
+                .expect("not a CAT1")[0];
+        assert_eq!(cat1, eve.wrapped_child(puzzle_hash, 1000));
+        assert!(sim.coin_state(cat1.coin.coin_id()).is_some());
+
+        // Melt the CAT1 and reissue it as a CAT2 with a new TAIL.
+        let parent_coin = sim.new_coin(puzzle_hash, 0);
+        let cat2_tail = ctx.curry(EverythingWithSignatureTailArgs::new(pk))?;
+        let memos = ctx.hint(puzzle_hash)?;
+
+        let (issue_cat2, cat2) = cat1.migrate(
+            ctx,
+            &p2,
+            Spend::new(tail, NodePtr::NIL),
+            parent_coin.coin_id(),
+            Spend::new(cat2_tail, NodePtr::NIL),
+            Conditions::new().create_coin(puzzle_hash, 1000, Some(memos)),
+        )?;
+        p2.spend(ctx, parent_coin, issue_cat2)?;
+        let coin_spends = ctx.take();
+
+        // The CAT2 can't be issued without melting the CAT1, even if it's backed by other XCH.
+        let funding = sim.new_coin(puzzle_hash, 1000);
+        p2.spend(ctx, funding, Conditions::new())?;
+        let mut without_melt = ctx.take();
+        without_melt.extend(
+            coin_spends
+                .iter()
+                .filter(|cs| cs.coin != cat1.coin)
+                .cloned(),
+        );
+        assert!(matches!(
+            sim.spend_coins(without_melt, &[sk.clone()]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::MessageNotSentOrReceived)
+        ));
+
+        // Likewise, the CAT1 can't be melted without issuing the CAT2.
+        let melt_only: Vec<_> = coin_spends
+            .iter()
+            .filter(|cs| cs.coin == cat1.coin)
+            .cloned()
+            .collect();
+        assert!(matches!(
+            sim.spend_coins(melt_only, &[sk.clone()]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::MessageNotSentOrReceived)
+        ));
+
+        sim.spend_coins(coin_spends, &[sk])?;
+
+        assert!(sim
+            .coin_state(cat1.coin.coin_id())
+            .is_some_and(|cs| cs.spent_height.is_some()));
+
+        let cat2 = cat2.wrapped_child(puzzle_hash, 1000);
+        assert!(sim.coin_state(cat2.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+}
@@ -137,6 +137,7 @@ mod tests {
         let clawback_coin = Coin::new(alice_coin.coin_id(), clawback_puzzle_hash, 1);
 
         sim.spend_coins(ctx.take(), &[alice_sk])?;
+        sim.pass_time(1);
 
         let bob_inner = bob.spend_with_conditions(ctx, Conditions::new().reserve_fee(1))?;
         let claim_spend = clawback.claim_spend(ctx, bob_inner)?;
@@ -13,10 +13,14 @@ use crate::{
     DidLayer, DriverError, Layer, Puzzle, SingletonLayer, Spend, SpendContext, SpendWithConditions,
 };
 
+mod did_authorization;
 mod did_info;
 mod did_launcher;
+mod did_recovery;
 
+pub use did_authorization::*;
 pub use did_info::*;
+pub use did_recovery::*;
 
 #[must_use]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,297 @@
+use chia_protocol::{Bytes, Bytes32};
+use chia_sdk_types::{announcement_id, Conditions, PUZZLE_TO_COIN_MESSAGE_MODE};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::Allocator;
+
+use crate::{DriverError, SpendContext, SpendWithConditions};
+
+use super::Did;
+
+/// The mode of messages sent by a DID to any coin with a puzzle hash. The sender is
+/// committed to by puzzle hash, and the receiver by puzzle hash.
+pub const DID_PUZZLE_MESSAGE_MODE: u8 = 0b010_010;
+
+/// How a DID authorizes other coins spent in the same transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DidAuthorization {
+    /// Creates a puzzle announcement, which any coin can assert.
+    Announcement(Bytes),
+    /// Sends a message to each coin, by coin id.
+    CoinMessages {
+        message: Bytes,
+        coin_ids: Vec<Bytes32>,
+    },
+    /// Sends a message to a coin with each puzzle hash.
+    PuzzleMessages {
+        message: Bytes,
+        puzzle_hashes: Vec<Bytes32>,
+    },
+}
+
+impl DidAuthorization {
+    /// Approves the spend of a coin whose custody has a `SingletonMember` for the DID,
+    /// such as a vault. The member receives the delegated puzzle hash itself, so the
+    /// returned assertions don't need to be included in the delegated spend.
+    pub fn singleton_member(delegated_puzzle_hash: Bytes32, coin_id: Bytes32) -> Self {
+        Self::CoinMessages {
+            message: delegated_puzzle_hash.to_vec().into(),
+            coin_ids: vec![coin_id],
+        }
+    }
+}
+
+impl<M> Did<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+{
+    /// Spends this DID to authorize other coins, and recreates it unchanged.
+    ///
+    /// Returns the conditions that each of the other coins must output to assert the
+    /// authorization, along with the child DID. For messages, every target must output
+    /// these conditions exactly once, since each message is received by a single coin.
+    pub fn authorize<I>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        kind: DidAuthorization,
+    ) -> Result<(Conditions, Did<M>), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let puzzle_hash = self.coin.puzzle_hash;
+
+        let (did_conditions, assertions) = match kind {
+            DidAuthorization::Announcement(message) => {
+                let announcement_id = announcement_id(puzzle_hash, &message);
+
+                (
+                    Conditions::new().create_puzzle_announcement(message),
+                    Conditions::new().assert_puzzle_announcement(announcement_id),
+                )
+            }
+            DidAuthorization::CoinMessages { message, coin_ids } => {
+                let mut did_conditions = Conditions::new();
+
+                for coin_id in coin_ids {
+                    let data = vec![ctx.alloc(&coin_id)?];
+                    did_conditions = did_conditions.send_message(
+                        PUZZLE_TO_COIN_MESSAGE_MODE,
+                        message.clone(),
+                        data,
+                    );
+                }
+
+                let data = vec![ctx.alloc(&puzzle_hash)?];
+                let assertions =
+                    Conditions::new().receive_message(PUZZLE_TO_COIN_MESSAGE_MODE, message, data);
+
+                (did_conditions, assertions)
+            }
+            DidAuthorization::PuzzleMessages {
+                message,
+                puzzle_hashes,
+            } => {
+                let mut did_conditions = Conditions::new();
+
+                for target_puzzle_hash in puzzle_hashes {
+                    let data = vec![ctx.alloc(&target_puzzle_hash)?];
+                    did_conditions =
+                        did_conditions.send_message(DID_PUZZLE_MESSAGE_MODE, message.clone(), data);
+                }
+
+                let data = vec![ctx.alloc(&puzzle_hash)?];
+                let assertions =
+                    Conditions::new().receive_message(DID_PUZZLE_MESSAGE_MODE, message, data);
+
+                (did_conditions, assertions)
+            }
+        };
+
+        let did = self.update(ctx, inner, did_conditions)?;
+
+        Ok((assertions, did))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_consensus::gen::validation_error::ErrorCode;
+    use chia_protocol::Coin;
+    use chia_sdk_test::{Simulator, SimulatorError};
+
+    use crate::{Launcher, StandardLayer};
+
+    use super::*;
+
+    #[test]
+    fn test_did_authorize_announcement() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let (assertions, did) = did.authorize(
+            ctx,
+            &p2,
+            DidAuthorization::Announcement(b"approved".to_vec().into()),
+        )?;
+        p2.spend(ctx, coin, assertions)?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+        assert_eq!(did.info.p2_puzzle_hash, puzzle_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_did_authorize_coin_messages() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let coins = [sim.new_coin(puzzle_hash, 1), sim.new_coin(puzzle_hash, 2)];
+        let (assertions, did) = did.authorize(
+            ctx,
+            &p2,
+            DidAuthorization::CoinMessages {
+                message: b"approved".to_vec().into(),
+                coin_ids: coins.iter().map(Coin::coin_id).collect(),
+            },
+        )?;
+
+        for coin in coins {
+            p2.spend(ctx, coin, assertions.clone())?;
+        }
+
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // Both messages must be received by the DID's target coins.
+        let coin = sim.new_coin(puzzle_hash, 3);
+        let (assertions, _did) = did.authorize(
+            ctx,
+            &p2,
+            DidAuthorization::CoinMessages {
+                message: b"approved".to_vec().into(),
+                coin_ids: vec![coin.coin_id(), Bytes32::default()],
+            },
+        )?;
+        p2.spend(ctx, coin, assertions)?;
+
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[sk]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::MessageNotSentOrReceived)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_did_authorize_puzzle_messages() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let (assertions, did) = did.authorize(
+            ctx,
+            &p2,
+            DidAuthorization::PuzzleMessages {
+                message: b"approved".to_vec().into(),
+                puzzle_hashes: vec![puzzle_hash],
+            },
+        )?;
+        p2.spend(ctx, coin, assertions)?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "experimental-vaults")]
+    #[test]
+    fn test_did_authorize_singleton_member() -> anyhow::Result<()> {
+        use chia_sdk_types::{Mod, SingletonMember, SingletonMemberSolution};
+
+        use crate::{MemberSpend, Spend, Vault, VaultSpend};
+
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // Mint a vault which is custodied by the DID.
+        let member = SingletonMember::new(did.info.launcher_id);
+        let custody_hash = Vault::custody_hash(0, Vec::new(), member.curry_tree_hash());
+
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let (mint_vault, vault) =
+            Launcher::new(coin.coin_id(), 1).mint_vault(ctx, custody_hash, ())?;
+        p2.spend(ctx, coin, mint_vault)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // The DID approves the delegated spend of the vault.
+        let conditions = Conditions::new().create_coin(vault.custody_hash.into(), 1, None);
+        let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
+        let delegated_puzzle_hash = ctx.tree_hash(spend.delegated.puzzle).into();
+
+        let member_puzzle = ctx.curry(member)?;
+        let member_solution = ctx.alloc(&SingletonMemberSolution::new(
+            did.info.inner_puzzle_hash().into(),
+            did.coin.amount,
+        ))?;
+        spend.members.insert(
+            custody_hash,
+            MemberSpend::new(0, Vec::new(), Spend::new(member_puzzle, member_solution)),
+        );
+
+        vault.spend(ctx, &spend)?;
+        let coin_spends = ctx.take();
+
+        // The vault can't be spent without the DID.
+        assert!(matches!(
+            sim.spend_coins(coin_spends.clone(), &[]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::MessageNotSentOrReceived)
+        ));
+
+        let (_, did) = did.authorize(
+            ctx,
+            &p2,
+            DidAuthorization::singleton_member(delegated_puzzle_hash, vault.coin.coin_id()),
+        )?;
+        let mut did_spends = ctx.take();
+        did_spends.extend(coin_spends);
+
+        sim.spend_coins(did_spends, &[sk])?;
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+        assert!(sim
+            .coin_state(vault.coin.coin_id())
+            .is_some_and(|cs| cs.spent_height.is_some()));
+
+        Ok(())
+    }
+}
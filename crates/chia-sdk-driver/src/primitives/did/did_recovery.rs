@@ -0,0 +1,462 @@
+use chia_bls::PublicKey;
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::singleton::{SingletonArgs, SingletonSolution};
+use chia_sdk_types::Conditions;
+use clvm_traits::{clvm_list, clvm_quote, FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::{
+    serde::{node_from_bytes, node_to_bytes},
+    Allocator, NodePtr,
+};
+
+use crate::{DriverError, Layer, Spend, SpendContext, SpendWithConditions};
+
+use super::Did;
+
+/// An approval from one of the DIDs in the recovery list of another DID, which allows it
+/// to be recovered to a new inner puzzle.
+///
+/// The attesting DID creates a message coin, which must be spent alongside the recovery.
+/// Attestations can be serialized with [`DidAttestation::to_bytes`] to be shared with
+/// whoever is recovering the DID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct DidAttestation {
+    /// The launcher id of the attesting DID.
+    pub attester_launcher_id: Bytes32,
+    /// The parent coin id of the attesting DID coin that created the message coin.
+    pub attester_parent_coin_info: Bytes32,
+    /// The inner puzzle hash of the attesting DID coin that created the message coin.
+    pub attester_inner_puzzle_hash: Bytes32,
+    /// The amount of the attesting DID coin that created the message coin.
+    pub attester_amount: u64,
+    /// The coin id of the DID being recovered.
+    pub recovering_coin_id: Bytes32,
+    /// The new inner puzzle hash of the DID being recovered.
+    pub new_inner_puzzle_hash: Bytes32,
+    /// The public key which must sign the recovery.
+    pub public_key: PublicKey,
+}
+
+impl DidAttestation {
+    /// The attesting DID coin that created the message coin.
+    pub fn attester_coin(&self) -> Coin {
+        Coin::new(
+            self.attester_parent_coin_info,
+            SingletonArgs::curry_tree_hash(
+                self.attester_launcher_id,
+                self.attester_inner_puzzle_hash.into(),
+            )
+            .into(),
+            self.attester_amount,
+        )
+    }
+
+    /// The puzzle of the message coin, which announces the recovering coin id and
+    /// requires a signature from the new public key.
+    pub fn message_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let conditions = Conditions::<NodePtr>::new()
+            .create_coin_announcement(self.recovering_coin_id.to_vec().into())
+            .agg_sig_unsafe(self.public_key, self.new_inner_puzzle_hash.to_vec().into());
+
+        ctx.alloc(&clvm_quote!(conditions))
+    }
+
+    /// The message coin, which must be spent alongside the recovery.
+    pub fn message_coin(&self, ctx: &mut SpendContext) -> Result<Coin, DriverError> {
+        let puzzle = self.message_puzzle(ctx)?;
+        Ok(Coin::new(
+            self.attester_coin().coin_id(),
+            ctx.tree_hash(puzzle).into(),
+            0,
+        ))
+    }
+
+    /// Spends the message coin.
+    pub fn spend(&self, ctx: &mut SpendContext) -> Result<(), DriverError> {
+        let puzzle = self.message_puzzle(ctx)?;
+        let coin = self.message_coin(ctx)?;
+        ctx.spend(coin, Spend::new(puzzle, NodePtr::NIL))
+    }
+
+    /// Serializes the attestation, so that it can be shared.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DriverError> {
+        let mut allocator = Allocator::new();
+        let ptr = self.to_clvm(&mut allocator)?;
+        Ok(node_to_bytes(&allocator, ptr)?)
+    }
+
+    /// Deserializes an attestation that was serialized with [`DidAttestation::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DriverError> {
+        let mut allocator = Allocator::new();
+        let ptr = node_from_bytes(&mut allocator, bytes)?;
+        Ok(Self::from_clvm(&allocator, ptr)?)
+    }
+}
+
+/// The information about an attesting DID coin that the DID puzzle needs to
+/// assert the announcement from its message coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+struct RecoveryCoinInfo {
+    parent_coin_info: Bytes32,
+    inner_puzzle_hash: Bytes32,
+    amount: u64,
+}
+
+impl<M> Did<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+{
+    /// Attests that another DID, which has this DID in its recovery list, can be
+    /// recovered to a new inner puzzle hash signed by the given public key.
+    ///
+    /// This DID is recreated with the same metadata, and the attestation is returned
+    /// along with the child DID.
+    pub fn create_attestation<I>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        recovering_coin_id: Bytes32,
+        new_inner_puzzle_hash: Bytes32,
+        public_key: PublicKey,
+        extra_conditions: Conditions,
+    ) -> Result<(DidAttestation, Did<M>), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let attestation = DidAttestation {
+            attester_launcher_id: self.info.launcher_id,
+            attester_parent_coin_info: self.coin.parent_coin_info,
+            attester_inner_puzzle_hash: self.info.inner_puzzle_hash().into(),
+            attester_amount: self.coin.amount,
+            recovering_coin_id,
+            new_inner_puzzle_hash,
+            public_key,
+        };
+
+        let message_puzzle = attestation.message_puzzle(ctx)?;
+        let message_puzzle_hash = ctx.tree_hash(message_puzzle).into();
+
+        let did = self.update(
+            ctx,
+            inner,
+            extra_conditions.create_coin(message_puzzle_hash, 0, None),
+        )?;
+
+        Ok((attestation, did))
+    }
+
+    /// Recovers this DID to a new p2 puzzle hash, using attestations from the DIDs in its
+    /// recovery list. The message coin of each attestation is spent as well.
+    ///
+    /// The recovery must be signed by the public key in the attestations, rather than
+    /// the current owner. The current p2 puzzle must still be revealed, since it's part
+    /// of the DID's puzzle.
+    ///
+    /// Note: The recovered DID is hinted with its new inner puzzle hash, rather than
+    /// the new p2 puzzle hash.
+    pub fn recover<I>(
+        self,
+        ctx: &mut SpendContext,
+        p2: &I,
+        new_p2_puzzle_hash: Bytes32,
+        public_key: PublicKey,
+        recovery_list: &[Bytes32],
+        attestations: &[DidAttestation],
+    ) -> Result<Did<M>, DriverError>
+    where
+        I: Layer,
+    {
+        let recovery_list_ptr = ctx.alloc(&recovery_list.to_vec())?;
+
+        if self.info.recovery_list_hash != Some(ctx.tree_hash(recovery_list_ptr).into()) {
+            return Err(DriverError::InvalidRecoveryList);
+        }
+
+        let coin_id = self.coin.coin_id();
+        let new_inner_puzzle_hash: Bytes32 = self
+            .info
+            .clone()
+            .with_p2_puzzle_hash(new_p2_puzzle_hash)
+            .inner_puzzle_hash()
+            .into();
+
+        for attestation in attestations {
+            if attestation.recovering_coin_id != coin_id
+                || attestation.new_inner_puzzle_hash != new_inner_puzzle_hash
+                || attestation.public_key != public_key
+                || !recovery_list.contains(&attestation.attester_launcher_id)
+            {
+                return Err(DriverError::InvalidAttestation);
+            }
+        }
+
+        let recovery_coins: Vec<Option<RecoveryCoinInfo>> = recovery_list
+            .iter()
+            .map(|launcher_id| {
+                attestations
+                    .iter()
+                    .find(|attestation| attestation.attester_launcher_id == *launcher_id)
+                    .map(|attestation| RecoveryCoinInfo {
+                        parent_coin_info: attestation.attester_parent_coin_info,
+                        inner_puzzle_hash: attestation.attester_inner_puzzle_hash,
+                        amount: attestation.attester_amount,
+                    })
+            })
+            .collect();
+
+        let found = u64::try_from(recovery_coins.iter().flatten().count())?;
+
+        if self.info.num_verifications_required == 0 || found < self.info.num_verifications_required
+        {
+            return Err(DriverError::MissingAttestations {
+                found,
+                required: self.info.num_verifications_required,
+            });
+        }
+
+        let p2_puzzle = p2.construct_puzzle(ctx)?;
+        let puzzle = self
+            .info
+            .clone()
+            .into_layers(p2_puzzle)
+            .construct_puzzle(ctx)?;
+
+        let recovery_solution = ctx.alloc(&clvm_list!(
+            0,
+            self.coin.amount,
+            new_inner_puzzle_hash,
+            recovery_coins,
+            public_key,
+            recovery_list_ptr,
+            coin_id
+        ))?;
+
+        let solution = ctx.alloc(&SingletonSolution {
+            lineage_proof: self.proof,
+            amount: self.coin.amount,
+            inner_solution: recovery_solution,
+        })?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))?;
+
+        for attestation in attestations {
+            attestation.spend(ctx)?;
+        }
+
+        let metadata = self.info.metadata.clone();
+
+        Ok(self.wrapped_child(new_p2_puzzle_hash, metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::SecretKey;
+    use chia_sdk_test::Simulator;
+
+    use crate::{Launcher, StandardLayer};
+
+    use super::*;
+
+    struct BackupDid {
+        sk: SecretKey,
+        p2: StandardLayer,
+        did: Did<()>,
+    }
+
+    struct RecoveryFixture {
+        backups: Vec<BackupDid>,
+        recovery_list: Vec<Bytes32>,
+        lost: StandardLayer,
+        did: Did<()>,
+        new_sk: SecretKey,
+        new_pk: PublicKey,
+        new_puzzle_hash: Bytes32,
+        new_inner_puzzle_hash: Bytes32,
+    }
+
+    /// Creates backup DIDs, each owned by a different key, and a DID which can be
+    /// recovered to a new owner with the given number of their attestations.
+    fn recovery_fixture(
+        sim: &mut Simulator,
+        ctx: &mut SpendContext,
+        backup_count: usize,
+        num_verifications_required: u64,
+    ) -> anyhow::Result<RecoveryFixture> {
+        let mut backups = Vec::new();
+        let mut secret_keys = Vec::new();
+
+        for _ in 0..backup_count {
+            let (sk, pk, _, coin) = sim.new_p2(1)?;
+            let p2 = StandardLayer::new(pk);
+            let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+            p2.spend(ctx, coin, create_did)?;
+            secret_keys.push(sk.clone());
+            backups.push(BackupDid { sk, p2, did });
+        }
+
+        let recovery_list: Vec<Bytes32> = backups
+            .iter()
+            .map(|backup| backup.did.info.launcher_id)
+            .collect();
+        let recovery_list_ptr = ctx.alloc(&recovery_list)?;
+        let recovery_list_hash = ctx.tree_hash(recovery_list_ptr).into();
+
+        let (sk, pk, _, coin) = sim.new_p2(1)?;
+        let lost = StandardLayer::new(pk);
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_did(
+            ctx,
+            Some(recovery_list_hash),
+            num_verifications_required,
+            (),
+            &lost,
+        )?;
+        lost.spend(ctx, coin, create_did)?;
+        secret_keys.push(sk);
+
+        sim.spend_coins(ctx.take(), &secret_keys)?;
+
+        // The key for the DID is lost, so it will be recovered to a new owner.
+        let (new_secret_key, new_public_key, new_puzzle_hash, _) = sim.new_p2(0)?;
+        let new_inner_puzzle_hash = did
+            .info
+            .with_p2_puzzle_hash(new_puzzle_hash)
+            .inner_puzzle_hash()
+            .into();
+
+        Ok(RecoveryFixture {
+            backups,
+            recovery_list,
+            lost,
+            did,
+            new_sk: new_secret_key,
+            new_pk: new_public_key,
+            new_puzzle_hash,
+            new_inner_puzzle_hash,
+        })
+    }
+
+    #[test]
+    fn test_did_recovery() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let fixture = recovery_fixture(&mut sim, ctx, 2, 2)?;
+
+        // Each backup DID attests to the new owner.
+        let mut attestations = Vec::new();
+        let mut secret_keys = Vec::new();
+
+        for backup in fixture.backups {
+            let (attestation, did) = backup.did.create_attestation(
+                ctx,
+                &backup.p2,
+                fixture.did.coin.coin_id(),
+                fixture.new_inner_puzzle_hash,
+                fixture.new_pk,
+                Conditions::new(),
+            )?;
+            sim.spend_coins(ctx.take(), &[backup.sk.clone()])?;
+            secret_keys.push(backup.sk);
+
+            assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+            // The attestation is shared with the new owner.
+            let shared = DidAttestation::from_bytes(&attestation.to_bytes()?)?;
+            assert_eq!(shared, attestation);
+
+            let message_coin = shared.message_coin(ctx)?;
+            assert!(sim.coin_state(message_coin.coin_id()).is_some());
+
+            attestations.push(shared);
+        }
+
+        // Recover the DID without the original key.
+        let did = fixture.did.recover(
+            ctx,
+            &fixture.lost,
+            fixture.new_puzzle_hash,
+            fixture.new_pk,
+            &fixture.recovery_list,
+            &attestations,
+        )?;
+        sim.spend_coins(ctx.take(), &[fixture.new_sk.clone()])?;
+
+        assert_eq!(did.info.p2_puzzle_hash, fixture.new_puzzle_hash);
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+        // The new owner can spend the DID.
+        let new_owner = StandardLayer::new(fixture.new_pk);
+        let did = did.update(ctx, &new_owner, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[fixture.new_sk])?;
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_did_recovery_missing_attestations() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let fixture = recovery_fixture(&mut sim, ctx, 2, 2)?;
+        let backup = &fixture.backups[0];
+
+        let (attestation, _) = backup.did.create_attestation(
+            ctx,
+            &backup.p2,
+            fixture.did.coin.coin_id(),
+            fixture.new_inner_puzzle_hash,
+            fixture.new_pk,
+            Conditions::new(),
+        )?;
+        sim.spend_coins(ctx.take(), &[backup.sk.clone()])?;
+
+        // A single attestation isn't enough.
+        assert!(matches!(
+            fixture.did.recover(
+                ctx,
+                &fixture.lost,
+                fixture.new_puzzle_hash,
+                fixture.new_pk,
+                &fixture.recovery_list,
+                &[attestation]
+            ),
+            Err(DriverError::MissingAttestations {
+                found: 1,
+                required: 2
+            })
+        ));
+
+        // The attestation can't be used for a different owner.
+        assert!(matches!(
+            fixture.did.recover(
+                ctx,
+                &fixture.lost,
+                Bytes32::default(),
+                fixture.new_pk,
+                &fixture.recovery_list,
+                &[attestation]
+            ),
+            Err(DriverError::InvalidAttestation)
+        ));
+
+        // The recovery list must match the DID.
+        assert!(matches!(
+            fixture.did.recover(
+                ctx,
+                &fixture.lost,
+                fixture.new_puzzle_hash,
+                fixture.new_pk,
+                &fixture.recovery_list[..1],
+                &[attestation]
+            ),
+            Err(DriverError::InvalidRecoveryList)
+        ));
+
+        Ok(())
+    }
+}
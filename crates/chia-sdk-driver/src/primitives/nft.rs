@@ -29,6 +29,18 @@ pub use metadata_update::*;
 pub use nft_info::*;
 pub use nft_mint::*;
 
+#[cfg(feature = "spend-cost")]
+mod bulk_minter;
+
+#[cfg(feature = "spend-cost")]
+pub use bulk_minter::*;
+
+#[cfg(feature = "chip-0007")]
+mod chip0007;
+
+#[cfg(feature = "chip-0007")]
+pub use chip0007::*;
+
 /// Everything that is required to spend an NFT coin.
 #[must_use]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -86,6 +98,13 @@ where
             .with_owner(owner)
             .with_metadata(metadata);
 
+        self.child_with_info(info)
+    }
+
+    fn child_with_info<N>(&self, info: NftInfo<N>) -> Nft<N>
+    where
+        N: ToTreeHash,
+    {
         let inner_puzzle_hash = info.inner_puzzle_hash();
 
         Nft {
@@ -178,11 +197,54 @@ where
         )?;
         let output = ctx.extract::<NewMetadataOutput<N, NodePtr>>(ptr)?;
 
-        Ok(self.wrapped_child(
+        let mut info = self
+            .info
+            .clone()
+            .with_p2_puzzle_hash(p2_puzzle_hash)
+            .with_metadata(output.metadata_info.new_metadata);
+        info.metadata_updater_puzzle_hash = output.metadata_info.new_updater_puzzle_hash;
+
+        Ok(self.child_with_info(info))
+    }
+
+    /// Updates the metadata of this NFT with a [`MetadataUpdater`], without changing its owner.
+    /// The updater puzzle must match the metadata updater puzzle hash of the NFT.
+    ///
+    /// The child is returned with the new metadata, which is parsed from the output of the updater.
+    pub fn update_metadata<I, U, N>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        updater: &U,
+        solution: U::Solution,
+        extra_conditions: Conditions,
+    ) -> Result<Nft<N>, DriverError>
+    where
+        I: SpendWithConditions,
+        U: MetadataUpdater,
+        N: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash,
+        M: ToTreeHash,
+    {
+        let metadata_update = updater.construct_spend(ctx, solution)?;
+
+        let updater_puzzle_hash = ctx.tree_hash(metadata_update.puzzle).into();
+
+        if updater_puzzle_hash != self.info.metadata_updater_puzzle_hash {
+            return Err(DriverError::MetadataUpdaterMismatch {
+                expected: self.info.metadata_updater_puzzle_hash,
+                found: updater_puzzle_hash,
+            });
+        }
+
+        let p2_puzzle_hash = self.info.p2_puzzle_hash;
+
+        self.transfer_with_metadata(
+            ctx,
+            inner,
             p2_puzzle_hash,
-            self.info.current_owner,
-            output.metadata_info.new_metadata,
-        ))
+            metadata_update,
+            extra_conditions,
+        )
     }
 
     /// Transfers this NFT to a new p2 puzzle hash.
@@ -503,6 +565,8 @@ mod tests {
 
     use chia_puzzles::nft::NftMetadata;
     use chia_sdk_test::Simulator;
+    use clvmr::serde::node_from_bytes;
+    use hex_literal::hex;
 
     #[test]
     fn test_nft_transfer() -> anyhow::Result<()> {
@@ -651,6 +715,117 @@ mod tests {
         Ok(())
     }
 
+    /// Replaces the metadata with the solution, and keeps the same updater puzzle hash.
+    struct ReplaceMetadataUpdater;
+
+    impl MetadataUpdater for ReplaceMetadataUpdater {
+        type Solution = NftMetadata;
+
+        fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+            Ok(node_from_bytes(
+                &mut ctx.allocator,
+                &hex!("ff04ffff04ff0bffff04ff05ffff01808080ffff01ff808080"),
+            )?)
+        }
+
+        fn construct_solution(
+            &self,
+            ctx: &mut SpendContext,
+            solution: Self::Solution,
+        ) -> Result<NodePtr, DriverError> {
+            ctx.alloc(&solution)
+        }
+    }
+
+    #[test]
+    fn test_custom_metadata_updater() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let updater = ReplaceMetadataUpdater.construct_puzzle(ctx)?;
+        let updater_puzzle_hash = ctx.tree_hash(updater).into();
+
+        let mint = NftMint::new(
+            NftMetadata::default(),
+            puzzle_hash,
+            300,
+            Some(DidOwner::from_did_info(&did.info)),
+        )
+        .with_custom_metadata_updater(updater_puzzle_hash);
+
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let _did = did.update(ctx, &p2, mint_nft)?;
+
+        // The default updater doesn't match the NFT.
+        assert!(matches!(
+            nft.clone().update_metadata::<_, _, NftMetadata>(
+                ctx,
+                &p2,
+                &DefaultMetadataUpdater,
+                MetadataUpdate::NewDataUri("example.com".to_string()),
+                Conditions::new(),
+            ),
+            Err(DriverError::MetadataUpdaterMismatch { .. })
+        ));
+
+        let new_metadata = NftMetadata {
+            edition_number: 2,
+            edition_total: 10,
+            data_uris: vec!["example.com".to_string()],
+            data_hash: Some(Bytes32::new([1; 32])),
+            metadata_uris: vec!["example.com/metadata.json".to_string()],
+            metadata_hash: Some(Bytes32::new([2; 32])),
+            ..Default::default()
+        };
+
+        let parent_nft = nft.clone();
+        let nft: Nft<NftMetadata> = nft.update_metadata(
+            ctx,
+            &p2,
+            &ReplaceMetadataUpdater,
+            new_metadata.clone(),
+            Conditions::new(),
+        )?;
+
+        assert_eq!(nft.info.metadata, new_metadata);
+        assert_eq!(nft.info.metadata_updater_puzzle_hash, updater_puzzle_hash);
+        assert_eq!(nft.info.p2_puzzle_hash, puzzle_hash);
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+        assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+
+        let parent_puzzle = sim
+            .puzzle_reveal(parent_nft.coin.coin_id())
+            .expect("missing puzzle");
+        let parent_solution = sim
+            .solution(parent_nft.coin.coin_id())
+            .expect("missing solution");
+
+        let parent_puzzle = parent_puzzle.to_clvm(&mut ctx.allocator)?;
+        let parent_puzzle = Puzzle::parse(&ctx.allocator, parent_puzzle);
+        let parent_solution = parent_solution.to_clvm(&mut ctx.allocator)?;
+
+        let parsed = Nft::<NftMetadata>::parse_child(
+            &mut ctx.allocator,
+            parent_nft.coin,
+            parent_puzzle,
+            parent_solution,
+        )?
+        .expect("child is not an NFT");
+
+        assert_eq!(parsed, nft);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_nft() -> anyhow::Result<()> {
         let mut sim = Simulator::new();
@@ -0,0 +1,372 @@
+use chia_protocol::{Coin, CoinSpend};
+use chia_sdk_types::Conditions;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::Allocator;
+
+use crate::{
+    calculate_spend_cost, Did, DriverError, IntermediateLauncher, SpendContext,
+    SpendWithConditions, MAX_SPEND_BUNDLE_COST,
+};
+
+use super::{DidOwner, Nft, NftMint};
+
+/// Mints a large number of NFTs on behalf of a DID, split into batches.
+///
+/// Each batch spends the DID once, creating an intermediate launcher for every NFT in the batch.
+/// The DID asserts each launcher, so the NFTs can't be minted without it.
+///
+/// Since each launcher holds a mojo, every batch also spends a funding coin to back them,
+/// and the change is used to fund the next batch. The funding coin also pays the fee of
+/// each batch, if there is one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkMinter {
+    /// The maximum number of NFTs minted in each batch.
+    pub batch_size: usize,
+    /// The maximum cost of each batch.
+    pub max_cost: u64,
+    /// The fee paid by each batch.
+    pub fee: u64,
+}
+
+/// The coin spends and NFTs for one batch of a bulk mint.
+#[derive(Debug, Clone)]
+pub struct BulkMintBatch<M> {
+    /// The coin spends, which must be submitted after those of the previous batch.
+    pub coin_spends: Vec<CoinSpend>,
+    /// The NFTs minted in this batch.
+    pub nfts: Vec<Nft<M>>,
+    /// The change from the funding coin, which funds the next batch.
+    /// There is no change if the funding coin was used up by the last batch.
+    pub change: Option<Coin>,
+    /// The cost of the coin spends.
+    pub cost: u64,
+}
+
+impl BulkMinter {
+    /// Creates a bulk minter whose batches fit in a single spend bundle.
+    pub fn new(batch_size: usize) -> Self {
+        Self {
+            batch_size,
+            max_cost: MAX_SPEND_BUNDLE_COST,
+            fee: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_cost(self, max_cost: u64) -> Self {
+        Self { max_cost, ..self }
+    }
+
+    /// Pays a fee with each batch, from the change of the funding coin.
+    #[must_use]
+    pub fn with_fee(self, fee: u64) -> Self {
+        Self { fee, ..self }
+    }
+
+    /// Mints the NFTs in batches, and returns each batch along with the latest DID.
+    ///
+    /// The funding coin must be owned by the same inner puzzle as the DID, and hold at least
+    /// one mojo for each NFT, plus the fee for each batch. NFTs without an owner are assigned
+    /// to the DID. Any coin spends already in the context are included in the first batch.
+    pub fn mint<M, N, I>(
+        &self,
+        ctx: &mut SpendContext,
+        did: Did<N>,
+        p2: &I,
+        funding: Coin,
+        mints: Vec<NftMint<M>>,
+    ) -> Result<(Vec<BulkMintBatch<M>>, Did<N>), DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        N: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        I: SpendWithConditions,
+    {
+        let mint_total = mints.len();
+        let batch_size = self.batch_size.max(1);
+        let batch_count = u64::try_from(mint_total.div_ceil(batch_size))?;
+
+        // Each launcher holds a single mojo, and each batch pays the fee.
+        let required =
+            u64::try_from(mint_total)?.saturating_add(self.fee.saturating_mul(batch_count));
+
+        if funding.amount < required {
+            return Err(DriverError::InsufficientFunding {
+                found: funding.amount,
+                required,
+            });
+        }
+
+        let did_owner = DidOwner::from_did_info(&did.info);
+
+        let mut did = did;
+        let mut funding = funding;
+        let mut batches = Vec::new();
+        let mut mints = mints.into_iter().enumerate().peekable();
+
+        while mints.peek().is_some() {
+            let mut did_conditions = Conditions::new();
+            let mut nfts = Vec::new();
+
+            for (mint_number, mint) in mints.by_ref().take(batch_size) {
+                let mint = NftMint {
+                    owner: mint.owner.or(Some(did_owner)),
+                    ..mint
+                };
+
+                let (mint_nft, nft) =
+                    IntermediateLauncher::new(did.coin.coin_id(), mint_number, mint_total)
+                        .create(ctx)?
+                        .mint_nft(ctx, mint)?;
+
+                did_conditions = did_conditions.extend(mint_nft);
+                nfts.push(nft);
+            }
+
+            // The funding was checked up front, so it only runs out in the last batch.
+            let change_amount = funding.amount - u64::try_from(nfts.len())? - self.fee;
+            let mut funding_conditions = Conditions::new();
+
+            if self.fee > 0 {
+                funding_conditions = funding_conditions.reserve_fee(self.fee);
+            }
+
+            let change = if change_amount > 0 {
+                funding_conditions =
+                    funding_conditions.create_coin(funding.puzzle_hash, change_amount, None);
+                Some(Coin::new(
+                    funding.coin_id(),
+                    funding.puzzle_hash,
+                    change_amount,
+                ))
+            } else {
+                None
+            };
+
+            let funding_spend = p2.spend_with_conditions(ctx, funding_conditions)?;
+            ctx.spend(funding, funding_spend)?;
+
+            did = did.update(ctx, p2, did_conditions)?;
+
+            let coin_spends = ctx.take();
+            let cost = calculate_spend_cost(&mut ctx.allocator, &coin_spends)?;
+
+            if cost > self.max_cost {
+                return Err(DriverError::CostExceeded {
+                    cost,
+                    max_cost: self.max_cost,
+                });
+            }
+
+            batches.push(BulkMintBatch {
+                coin_spends,
+                nfts,
+                change,
+                cost,
+            });
+
+            if let Some(change) = change {
+                funding = change;
+            }
+        }
+
+        Ok((batches, did))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::Simulator;
+
+    use crate::{Launcher, StandardLayer};
+
+    use super::*;
+
+    #[test]
+    fn test_bulk_minter() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let funding = sim.new_coin(puzzle_hash, 10);
+
+        let mints = (0..5)
+            .map(|i| {
+                NftMint::new(
+                    NftMetadata {
+                        edition_number: i + 1,
+                        edition_total: 5,
+                        ..Default::default()
+                    },
+                    puzzle_hash,
+                    300,
+                    None,
+                )
+            })
+            .collect();
+
+        let (batches, did) = BulkMinter::new(2).mint(ctx, did, &p2, funding, mints)?;
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.nfts.len())
+                .collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+
+        for batch in batches.iter().cloned() {
+            assert!(batch.cost <= MAX_SPEND_BUNDLE_COST);
+            sim.spend_coins(batch.coin_spends, &[sk.clone()])?;
+        }
+
+        for nft in batches.iter().flat_map(|batch| &batch.nfts) {
+            assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+            assert_eq!(nft.info.current_owner, Some(did.info.launcher_id));
+        }
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+        let change = batches.last().unwrap().change.unwrap();
+        assert_eq!(change.amount, 5);
+        assert!(sim.coin_state(change.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_minter_max_cost() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let funding = sim.new_coin(puzzle_hash, 2);
+        let mints = vec![NftMint::new(NftMetadata::default(), puzzle_hash, 300, None); 3];
+
+        assert!(matches!(
+            BulkMinter::new(3).mint(ctx, did, &p2, funding, mints.clone()),
+            Err(DriverError::InsufficientFunding {
+                found: 2,
+                required: 3
+            })
+        ));
+        ctx.take();
+
+        let funding = sim.new_coin(puzzle_hash, 3);
+
+        assert!(matches!(
+            BulkMinter::new(3)
+                .with_max_cost(100_000_000)
+                .mint(ctx, did, &p2, funding, mints),
+            Err(DriverError::CostExceeded { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_minter_fee() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let mints = vec![NftMint::new(NftMetadata::default(), puzzle_hash, 300, None); 5];
+
+        // Each of the 3 batches pays the fee, on top of a mojo for each NFT.
+        let funding = sim.new_coin(puzzle_hash, 34);
+
+        assert!(matches!(
+            BulkMinter::new(2)
+                .with_fee(10)
+                .mint(ctx, did, &p2, funding, mints.clone()),
+            Err(DriverError::InsufficientFunding {
+                found: 34,
+                required: 35
+            })
+        ));
+        ctx.take();
+
+        let funding = sim.new_coin(puzzle_hash, 40);
+
+        let (batches, did) = BulkMinter::new(2)
+            .with_fee(10)
+            .mint(ctx, did, &p2, funding, mints)?;
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.change.map(|coin| coin.amount))
+                .collect::<Vec<_>>(),
+            [Some(28), Some(16), Some(5)]
+        );
+
+        // Each batch spends the change of the previous one, so they're submitted in order.
+        for batch in batches.iter().cloned() {
+            sim.spend_coins(batch.coin_spends, &[sk.clone()])?;
+        }
+
+        for nft in batches.iter().flat_map(|batch| &batch.nfts) {
+            assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+        }
+
+        assert!(sim.coin_state(did.coin.coin_id()).is_some());
+
+        let change = batches.last().unwrap().change.unwrap();
+        assert!(sim.coin_state(change.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_minter_exact_funding() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let funding = sim.new_coin(puzzle_hash, 3);
+        let mints = vec![NftMint::new(NftMetadata::default(), puzzle_hash, 300, None); 3];
+
+        let (batches, _did) = BulkMinter::new(2).mint(ctx, did, &p2, funding, mints)?;
+
+        // The last batch uses up the funding coin, so it doesn't create any change.
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.change.map(|coin| coin.amount))
+                .collect::<Vec<_>>(),
+            [Some(1), None]
+        );
+
+        for batch in batches {
+            sim.spend_coins(batch.coin_spends, &[sk.clone()])?;
+        }
+
+        assert!(sim
+            .children(funding.coin_id())
+            .iter()
+            .all(|cs| cs.spent_height.is_some()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+use chia_protocol::Bytes32;
+use chia_puzzles::nft::NftMetadata;
+use clvmr::sha2::Sha256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{DidOwner, NftMint};
+
+/// The value of the `format` field in CHIP-0007 metadata.
+pub const CHIP_0007_FORMAT: &str = "CHIP-0007";
+
+#[derive(Debug, Error)]
+pub enum Chip0007Error {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unsupported metadata format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("The NFT has no metadata hash")]
+    MissingMetadataHash,
+
+    #[error("Metadata hash mismatch, expected {expected} but found {found}")]
+    MetadataHashMismatch { expected: Bytes32, found: Bytes32 },
+
+    #[error("Metadata belongs to more than one collection")]
+    MixedCollections,
+}
+
+/// The off-chain metadata of an NFT, as defined by CHIP-0007.
+///
+/// This is the JSON document that the metadata URIs of an NFT point to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chip0007Metadata {
+    pub format: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minting_tool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive_content: Option<SensitiveContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<NftAttribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<NftCollection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Whether an NFT contains sensitive content, either as a flag or a list of the kinds of content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SensitiveContent {
+    Flag(bool),
+    Items(Vec<String>),
+}
+
+/// A trait of an individual NFT, such as its rarity or a numeric stat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftAttribute {
+    pub trait_type: AttributeValue,
+    pub value: AttributeValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<serde_json::Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<serde_json::Number>,
+}
+
+/// A string or numeric value of an attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    String(String),
+    Number(serde_json::Number),
+}
+
+/// The collection that an NFT belongs to, which is shared between all of its NFTs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftCollection {
+    /// A UUID which uniquely identifies the collection.
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<CollectionAttribute>,
+}
+
+/// An attribute of a collection, such as its description, icon, banner, or website.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionAttribute {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: AttributeValue,
+}
+
+impl Chip0007Metadata {
+    /// Parses the metadata from JSON, checking that it's in the CHIP-0007 format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Chip0007Error> {
+        let metadata: Self = serde_json::from_slice(bytes)?;
+
+        if metadata.format != CHIP_0007_FORMAT {
+            return Err(Chip0007Error::UnsupportedFormat(metadata.format));
+        }
+
+        Ok(metadata)
+    }
+
+    /// Parses the metadata from JSON, after checking that it matches the on-chain metadata hash.
+    pub fn from_verified_bytes(
+        bytes: &[u8],
+        nft_metadata: &NftMetadata,
+    ) -> Result<Self, Chip0007Error> {
+        let expected = nft_metadata
+            .metadata_hash
+            .ok_or(Chip0007Error::MissingMetadataHash)?;
+
+        verify_metadata_hash(bytes, expected)?;
+
+        Self::from_bytes(bytes)
+    }
+
+    /// Serializes the metadata to JSON.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Chip0007Error> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+/// Calculates the hash of metadata JSON, which is committed to on-chain in [`NftMetadata::metadata_hash`].
+pub fn metadata_hash(bytes: &[u8]) -> Bytes32 {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+/// Checks that the hash of metadata JSON matches the on-chain metadata hash.
+pub fn verify_metadata_hash(bytes: &[u8], expected: Bytes32) -> Result<(), Chip0007Error> {
+    let found = metadata_hash(bytes);
+
+    if found != expected {
+        return Err(Chip0007Error::MetadataHashMismatch { expected, found });
+    }
+
+    Ok(())
+}
+
+/// An NFT in a mint manifest, along with the JSON of the CHIP-0007 metadata it points to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Chip0007ManifestEntry {
+    pub data_uris: Vec<String>,
+    pub data_hash: Option<Bytes32>,
+    pub metadata_uris: Vec<String>,
+    pub metadata_json: Vec<u8>,
+    pub license_uris: Vec<String>,
+    pub license_hash: Option<Bytes32>,
+}
+
+impl Chip0007ManifestEntry {
+    /// Parses the CHIP-0007 metadata of this entry.
+    pub fn chip0007_metadata(&self) -> Result<Chip0007Metadata, Chip0007Error> {
+        Chip0007Metadata::from_bytes(&self.metadata_json)
+    }
+
+    /// Creates the on-chain metadata for this entry. The edition number and total are
+    /// taken from the series of the CHIP-0007 metadata, and default to 1.
+    pub fn nft_metadata(&self) -> Result<NftMetadata, Chip0007Error> {
+        Ok(self.nft_metadata_from(&self.chip0007_metadata()?))
+    }
+
+    fn nft_metadata_from(&self, metadata: &Chip0007Metadata) -> NftMetadata {
+        NftMetadata {
+            edition_number: metadata.series_number.unwrap_or(1),
+            edition_total: metadata.series_total.unwrap_or(1),
+            data_uris: self.data_uris.clone(),
+            data_hash: self.data_hash,
+            metadata_uris: self.metadata_uris.clone(),
+            metadata_hash: Some(metadata_hash(&self.metadata_json)),
+            license_uris: self.license_uris.clone(),
+            license_hash: self.license_hash,
+        }
+    }
+}
+
+/// Creates an [`NftMint`] for each entry in a CHIP-0007 manifest.
+///
+/// All of the entries must belong to the same collection, if any.
+pub fn nft_mints_from_manifest(
+    entries: &[Chip0007ManifestEntry],
+    p2_puzzle_hash: Bytes32,
+    royalty_ten_thousandths: u16,
+    owner: Option<DidOwner>,
+) -> Result<Vec<NftMint<NftMetadata>>, Chip0007Error> {
+    let mut collection_id = None;
+    let mut mints = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let metadata = entry.chip0007_metadata()?;
+        let id = metadata
+            .collection
+            .as_ref()
+            .map(|collection| &collection.id);
+
+        if mints.is_empty() {
+            collection_id = id.cloned();
+        } else if id != collection_id.as_ref() {
+            return Err(Chip0007Error::MixedCollections);
+        }
+
+        mints.push(NftMint::new(
+            entry.nft_metadata_from(&metadata),
+            p2_puzzle_hash,
+            royalty_ten_thousandths,
+            owner,
+        ));
+    }
+
+    Ok(mints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "format": "CHIP-0007",
+        "name": "Pikachu",
+        "description": "Electric-type Pokémon with stretchy cheeks",
+        "minting_tool": "SuperMinter/2.5.2",
+        "sensitive_content": false,
+        "series_number": 22,
+        "series_total": 1000,
+        "attributes": [
+            { "trait_type": "Species", "value": "Mouse" },
+            { "trait_type": "Speed", "value": 50, "min_value": 0, "max_value": 100 }
+        ],
+        "collection": {
+            "id": "e43fcfe6-1d5c-4d6e-82da-5de3aa8b3b57",
+            "name": "Example Pokémon Collection",
+            "attributes": [
+                { "type": "description", "value": "Example Pokémon Collection is the best Pokémon collection." },
+                { "type": "icon", "value": "https://examplepokemoncollection.com/image/icon.png" }
+            ]
+        },
+        "data": { "example_data": "VGhpcyBpcyBhbiBleGFtcGxlIG9mIGRhdGEgdGhhdCB5b3UgbWlnaHQgd2FudCB0byBzdG9yZSBpbiB0aGUgZGF0YSBvYmplY3QuIE5GVCBhdHRyaWJ1dGVzIHdoaWNoIGFyZSBub3QgaHVtYW4gcmVhZGFibGUgc2hvdWxkIGJlIHBsYWNlZCB3aXRoaW4gdGhpcyBvYmplY3QsIGFuZCB0aGUgYXR0cmlidXRlcyBhcnJheSBzaG91bGQgb25seSBiZSB1c2VkIGZvciBhdHRyaWJ1dGVzIHdoaWNoIGFyZSBpbnRlbmRlZCB0byBiZSBkaXNwbGF5ZWQgdG8gdGhlIHVzZXIu" }
+    }"#;
+
+    fn entry(json: &str) -> Chip0007ManifestEntry {
+        Chip0007ManifestEntry {
+            data_uris: vec!["https://example.com/data.png".to_string()],
+            data_hash: Some(Bytes32::new([1; 32])),
+            metadata_uris: vec!["https://example.com/metadata.json".to_string()],
+            metadata_json: json.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chip0007_roundtrip() -> anyhow::Result<()> {
+        let metadata = Chip0007Metadata::from_bytes(METADATA.as_bytes())?;
+
+        assert_eq!(metadata.name, "Pikachu");
+        assert_eq!(metadata.series_number, Some(22));
+        assert_eq!(
+            metadata.sensitive_content,
+            Some(SensitiveContent::Flag(false))
+        );
+        assert_eq!(metadata.attributes.len(), 2);
+        assert_eq!(
+            metadata.attributes[1].value,
+            AttributeValue::Number(50.into())
+        );
+
+        let collection = metadata.collection.clone().expect("missing collection");
+        assert_eq!(collection.attributes[1].kind, "icon");
+
+        let bytes = metadata.to_bytes()?;
+        assert_eq!(Chip0007Metadata::from_bytes(&bytes)?, metadata);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chip0007_unsupported_format() {
+        let json = METADATA.replace("CHIP-0007", "CHIP-9999");
+
+        assert!(matches!(
+            Chip0007Metadata::from_bytes(json.as_bytes()),
+            Err(Chip0007Error::UnsupportedFormat(format)) if format == "CHIP-9999"
+        ));
+    }
+
+    #[test]
+    fn test_chip0007_verify_metadata_hash() -> anyhow::Result<()> {
+        let nft_metadata = entry(METADATA).nft_metadata()?;
+
+        assert_eq!(nft_metadata.edition_number, 22);
+        assert_eq!(nft_metadata.edition_total, 1000);
+        assert_eq!(
+            Chip0007Metadata::from_verified_bytes(METADATA.as_bytes(), &nft_metadata)?,
+            Chip0007Metadata::from_bytes(METADATA.as_bytes())?
+        );
+
+        let tampered = METADATA.replace("Pikachu", "Raichu");
+        assert!(matches!(
+            Chip0007Metadata::from_verified_bytes(tampered.as_bytes(), &nft_metadata),
+            Err(Chip0007Error::MetadataHashMismatch { .. })
+        ));
+
+        assert!(matches!(
+            Chip0007Metadata::from_verified_bytes(METADATA.as_bytes(), &NftMetadata::default()),
+            Err(Chip0007Error::MissingMetadataHash)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_mints_from_manifest() -> anyhow::Result<()> {
+        let p2_puzzle_hash = Bytes32::new([2; 32]);
+        let entries = [
+            entry(METADATA),
+            entry(&METADATA.replace("\"series_number\": 22", "\"series_number\": 23")),
+        ];
+
+        let mints = nft_mints_from_manifest(&entries, p2_puzzle_hash, 300, None)?;
+
+        assert_eq!(mints.len(), 2);
+        assert_eq!(mints[0].metadata.edition_number, 22);
+        assert_eq!(mints[1].metadata.edition_number, 23);
+        assert_eq!(mints[1].p2_puzzle_hash, p2_puzzle_hash);
+        assert_eq!(mints[1].royalty_ten_thousandths, 300);
+        assert_eq!(
+            mints[1].metadata.metadata_hash,
+            Some(metadata_hash(&entries[1].metadata_json))
+        );
+
+        let other = METADATA.replace(
+            "e43fcfe6-1d5c-4d6e-82da-5de3aa8b3b57",
+            "0f6ed8b7-1e0c-4b5d-9c8a-3a2f8f1d6c42",
+        );
+        assert!(matches!(
+            nft_mints_from_manifest(&[entry(METADATA), entry(&other)], p2_puzzle_hash, 300, None),
+            Err(Chip0007Error::MixedCollections)
+        ));
+
+        Ok(())
+    }
+}
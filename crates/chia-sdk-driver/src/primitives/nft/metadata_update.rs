@@ -1,3 +1,5 @@
+use clvmr::NodePtr;
+
 use crate::{DriverError, Spend, SpendContext};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -9,11 +11,60 @@ pub enum MetadataUpdate {
 
 impl MetadataUpdate {
     pub fn spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
-        let solution = ctx.alloc(&match self {
-            Self::NewDataUri(uri) => ("u", uri),
-            Self::NewMetadataUri(uri) => ("mu", uri),
-            Self::NewLicenseUri(uri) => ("lu", uri),
-        })?;
-        Ok(Spend::new(ctx.nft_metadata_updater()?, solution))
+        DefaultMetadataUpdater.construct_spend(ctx, self.clone())
+    }
+}
+
+/// A puzzle that can be revealed in the [`UpdateNftMetadata`](chia_sdk_types::UpdateNftMetadata)
+/// condition to update the metadata of an NFT.
+///
+/// The updater is run with the current metadata, the current updater puzzle hash, and the solution.
+/// It must output the new metadata and updater puzzle hash, followed by a list of conditions.
+pub trait MetadataUpdater {
+    type Solution;
+
+    /// Constructs the updater puzzle. Its tree hash must match the updater puzzle hash of the NFT.
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError>;
+
+    /// Constructs the solution for the updater puzzle.
+    fn construct_solution(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError>;
+
+    /// Constructs the updater puzzle and solution, for use in [`Nft::update_metadata`](super::Nft::update_metadata).
+    fn construct_spend(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<Spend, DriverError> {
+        let puzzle = self.construct_puzzle(ctx)?;
+        let solution = self.construct_solution(ctx, solution)?;
+        Ok(Spend::new(puzzle, solution))
+    }
+}
+
+/// The default metadata updater, which can only add URIs to [`NftMetadata`](chia_puzzles::nft::NftMetadata).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultMetadataUpdater;
+
+impl MetadataUpdater for DefaultMetadataUpdater {
+    type Solution = MetadataUpdate;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        ctx.nft_metadata_updater()
+    }
+
+    fn construct_solution(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        ctx.alloc(&match &solution {
+            MetadataUpdate::NewDataUri(uri) => ("u", uri),
+            MetadataUpdate::NewMetadataUri(uri) => ("mu", uri),
+            MetadataUpdate::NewLicenseUri(uri) => ("lu", uri),
+        })
     }
 }
@@ -1,14 +1,29 @@
+mod bls_taproot;
+mod custody_config;
 mod m_of_n;
 mod member;
 mod member_kind;
 mod restriction;
+mod vault_assets;
 mod vault_launcher;
+mod vault_migration;
+mod vault_policy;
+mod vault_rate_limit;
+mod vault_recovery;
 mod vault_spend;
+mod vault_update;
 
+pub use bls_taproot::*;
+pub use custody_config::*;
 pub use m_of_n::*;
 pub use member::*;
 pub use member_kind::*;
 pub use restriction::*;
+pub use vault_launcher::*;
+pub use vault_migration::*;
+pub use vault_policy::*;
+pub use vault_rate_limit::*;
+pub use vault_recovery::*;
 pub use vault_spend::*;
 
 use chia_protocol::{Bytes32, Coin};
@@ -16,11 +31,14 @@ use chia_puzzles::{
     singleton::{SingletonArgs, SingletonSolution},
     LineageProof, Proof,
 };
-use clvm_utils::TreeHash;
+use chia_sdk_types::{run_puzzle, Condition, DelegatedFeederArgs, IndexWrapperArgs, Mod};
+use clvm_traits::FromClvm;
+use clvm_utils::{tree_hash, TreeHash};
+use clvmr::{Allocator, NodePtr};
 
-use crate::{DriverError, Spend, SpendContext};
+use crate::{DriverError, Layer, Puzzle, SingletonLayer, Spend, SpendContext};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Vault {
     pub coin: Coin,
     pub launcher_id: Bytes32,
@@ -56,11 +74,16 @@ impl Vault {
 
     #[must_use]
     pub fn child(&self, custody_hash: TreeHash) -> Self {
+        self.child_with_amount(custody_hash, self.coin.amount)
+    }
+
+    #[must_use]
+    pub fn child_with_amount(&self, custody_hash: TreeHash, amount: u64) -> Self {
         Self {
             coin: Coin::new(
                 self.coin.coin_id(),
                 SingletonArgs::curry_tree_hash(self.launcher_id, custody_hash).into(),
-                self.coin.amount,
+                amount,
             ),
             launcher_id: self.launcher_id,
             proof: Proof::Lineage(self.child_lineage_proof()),
@@ -86,13 +109,111 @@ impl Vault {
 
         Ok(())
     }
+
+    /// Parses the child of a vault from the parent coin spend, including the new custody hash.
+    ///
+    /// Returns [`None`] if the parent isn't a vault, or if it was melted. The custody puzzle of the
+    /// parent must be revealed in the spend, which is always the case since it's the singleton's
+    /// inner puzzle.
+    pub fn parse_child(
+        allocator: &mut Allocator,
+        parent_coin: Coin,
+        parent_puzzle: Puzzle,
+        parent_solution: NodePtr,
+    ) -> Result<Option<Self>, DriverError> {
+        let Some(singleton_layer) =
+            SingletonLayer::<Puzzle>::parse_puzzle(allocator, parent_puzzle)?
+        else {
+            return Ok(None);
+        };
+
+        let Some(index_wrapper) = singleton_layer.inner_puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if index_wrapper.mod_hash != IndexWrapperArgs::<NodePtr>::MOD_HASH {
+            return Ok(None);
+        }
+
+        let args = IndexWrapperArgs::<NodePtr>::from_clvm(allocator, index_wrapper.args)?;
+        let delegated_feeder = Puzzle::parse(allocator, args.inner_puzzle);
+
+        if delegated_feeder.mod_hash() != DelegatedFeederArgs::<NodePtr>::MOD_HASH {
+            return Ok(None);
+        }
+
+        let singleton_solution =
+            SingletonLayer::<Puzzle>::parse_solution(allocator, parent_solution)?;
+
+        let output = run_puzzle(
+            allocator,
+            singleton_layer.inner_puzzle.ptr(),
+            singleton_solution.inner_solution,
+        )?;
+        let conditions = Vec::<NodePtr>::from_clvm(allocator, output)?;
+
+        let mut create_coin = None;
+
+        for condition in conditions {
+            if is_melt_condition(allocator, condition) {
+                return Ok(None);
+            }
+
+            if let Condition::CreateCoin(condition) =
+                Condition::<NodePtr>::from_clvm(allocator, condition)?
+            {
+                if condition.amount % 2 == 1 {
+                    create_coin = Some(condition);
+                }
+            }
+        }
+
+        let Some(create_coin) = create_coin else {
+            return Err(DriverError::MissingChild);
+        };
+
+        let parent_custody_hash = tree_hash(allocator, singleton_layer.inner_puzzle.ptr());
+        let custody_hash = TreeHash::from(create_coin.puzzle_hash);
+
+        Ok(Some(Self {
+            coin: Coin::new(
+                parent_coin.coin_id(),
+                SingletonArgs::curry_tree_hash(singleton_layer.launcher_id, custody_hash).into(),
+                create_coin.amount,
+            ),
+            launcher_id: singleton_layer.launcher_id,
+            proof: Proof::Lineage(LineageProof {
+                parent_parent_coin_info: parent_coin.parent_coin_info,
+                parent_inner_puzzle_hash: parent_custody_hash.into(),
+                parent_amount: parent_coin.amount,
+            }),
+            custody_hash,
+        }))
+    }
+}
+
+/// Whether the condition is a `CREATE_COIN` with the magic amount of -113, which melts the
+/// singleton. This must be checked before parsing the condition, since the amount would
+/// otherwise be read as an unsigned odd amount.
+fn is_melt_condition(allocator: &Allocator, condition: NodePtr) -> bool {
+    <(u8, (NodePtr, (i64, NodePtr)))>::from_clvm(allocator, condition)
+        .is_ok_and(|(opcode, (_, (amount, _)))| opcode == 51 && amount == -113)
 }
 
 #[cfg(test)]
 mod tests {
-    use chia_sdk_test::{test_k1_key, test_k1_keys, Simulator};
-    use chia_sdk_types::{Conditions, Mod, Secp256k1Member, Secp256k1MemberSolution};
-    use chia_secp::{K1SecretKey, K1Signature};
+    use std::collections::HashMap;
+
+    use chia_sdk_signer::passkey_challenge;
+    use chia_sdk_test::{
+        test_k1_key, test_k1_keys, test_r1_key, test_secret_key, Simulator, SoftwarePasskey,
+    };
+    use chia_sdk_types::{
+        Conditions, MeltSingleton, Mod, Secp256k1Member, Secp256k1MemberSolution,
+        TESTNET11_CONSTANTS,
+    };
+    use chia_secp::K1SecretKey;
+    use clvm_traits::{clvm_list, ToClvm};
     use clvmr::sha2::Sha256;
     use rstest::rstest;
 
@@ -100,16 +221,34 @@ mod tests {
 
     use super::*;
 
-    fn mint_vault(
+    pub(super) fn mint_vault(
         sim: &mut Simulator,
         ctx: &mut SpendContext,
         custody_hash: TreeHash,
     ) -> anyhow::Result<Vault> {
-        let (sk, pk, _puzzle_hash, coin) = sim.new_p2(1)?;
-        let p2 = StandardLayer::new(pk);
+        let (_sk, _pk, _puzzle_hash, coin) = sim.new_p2(1)?;
+        mint_vault_from(
+            sim,
+            ctx,
+            coin,
+            Launcher::new(coin.coin_id(), 1),
+            custody_hash,
+        )
+    }
 
-        let (mint_vault, vault) =
-            Launcher::new(coin.coin_id(), 1).mint_vault(ctx, custody_hash, ())?;
+    /// Mints a vault with a launcher created by a coin locked to the test key, so that the
+    /// launcher id can be known before the custody hash is.
+    pub(super) fn mint_vault_from(
+        sim: &mut Simulator,
+        ctx: &mut SpendContext,
+        coin: Coin,
+        launcher: Launcher,
+        custody_hash: TreeHash,
+    ) -> anyhow::Result<Vault> {
+        let sk = test_secret_key()?;
+        let p2 = StandardLayer::new(sk.public_key());
+
+        let (mint_vault, vault) = launcher.mint_vault(ctx, custody_hash, ())?;
         p2.spend(ctx, coin, mint_vault)?;
 
         sim.spend_coins(ctx.take(), &[sk])?;
@@ -117,16 +256,30 @@ mod tests {
         Ok(vault)
     }
 
-    fn k1_sign(
-        ctx: &SpendContext,
+    /// A custody configuration with a single K1 key.
+    pub(super) fn k1_member(k1: &K1SecretKey) -> CustodyConfig {
+        CustodyConfig::new(CustodyMember::K1 {
+            public_key: k1.public_key(),
+            fast_forward: false,
+        })
+    }
+
+    /// Signs the delegated spend of a vault with a K1 key, and returns the member solution.
+    pub(super) fn k1_solution(
+        ctx: &mut SpendContext,
         vault: &Vault,
-        spend: &VaultSpend,
+        delegated_spend: Spend,
         k1: &K1SecretKey,
-    ) -> anyhow::Result<K1Signature> {
+    ) -> anyhow::Result<NodePtr> {
         let mut hasher = Sha256::new();
-        hasher.update(ctx.tree_hash(spend.delegated.puzzle));
+        hasher.update(ctx.tree_hash(delegated_spend.puzzle));
         hasher.update(vault.coin.coin_id());
-        Ok(k1.sign_prehashed(&hasher.finalize())?)
+        let signature = k1.sign_prehashed(&hasher.finalize())?;
+
+        Ok(ctx.alloc(&Secp256k1MemberSolution::new(
+            vault.coin.coin_id(),
+            signature,
+        ))?)
     }
 
     #[test]
@@ -143,16 +296,12 @@ mod tests {
         let conditions = Conditions::new().create_coin(vault.custody_hash.into(), 1, None);
         let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
 
-        let signature = k1_sign(ctx, &vault, &spend, &k1)?;
         let k1_puzzle = ctx.curry(custody)?;
-        let k1_solution = ctx.alloc(&Secp256k1MemberSolution::new(
-            vault.coin.coin_id(),
-            signature,
-        ))?;
+        let solution = k1_solution(ctx, &vault, spend.delegated, &k1)?;
 
         spend.members.insert(
             custody_hash,
-            MemberSpend::new(0, Vec::new(), Spend::new(k1_puzzle, k1_solution)),
+            MemberSpend::new(0, Vec::new(), Spend::new(k1_puzzle, solution)),
         );
 
         vault.spend(ctx, &spend)?;
@@ -206,17 +355,12 @@ mod tests {
             let mut i = start;
 
             for _ in 0..required {
-                let signature = k1_sign(ctx, &vault, &spend, &keys[i])?;
-
                 let k1_puzzle = ctx.curry(members[i])?;
-                let k1_solution = ctx.alloc(&Secp256k1MemberSolution::new(
-                    vault.coin.coin_id(),
-                    signature,
-                ))?;
+                let solution = k1_solution(ctx, &vault, spend.delegated, &keys[i])?;
 
                 spend.members.insert(
                     hashes[i],
-                    MemberSpend::new(0, Vec::new(), Spend::new(k1_puzzle, k1_solution)),
+                    MemberSpend::new(0, Vec::new(), Spend::new(k1_puzzle, solution)),
                 );
 
                 i += 1;
@@ -234,4 +378,177 @@ mod tests {
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::passkey(false)]
+    #[case::passkey_puzzle_assert(true)]
+    fn test_passkey_vault(#[case] fast_forward: bool) -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let mut passkey = SoftwarePasskey::new(test_r1_key()?);
+        let genesis_challenge = TESTNET11_CONSTANTS.genesis_challenge;
+
+        let custody = CustodyConfig::new(CustodyMember::Passkey {
+            genesis_challenge,
+            public_key: passkey.public_key(),
+            fast_forward,
+        });
+        let custody_hash = custody.custody_hash();
+
+        let vault = mint_vault(&mut sim, ctx, custody_hash)?;
+
+        let conditions = Conditions::new().create_coin(vault.custody_hash.into(), 1, None);
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+        let delegated_puzzle_hash = ctx.tree_hash(delegated_spend.puzzle).into();
+
+        let solution = if fast_forward {
+            let puzzle_hash = vault.coin.puzzle_hash;
+            let challenge =
+                passkey_challenge(delegated_puzzle_hash, puzzle_hash, genesis_challenge);
+            ctx.alloc(&passkey.sign(challenge)?.member_puzzle_assert_solution(
+                genesis_challenge,
+                delegated_puzzle_hash,
+                puzzle_hash,
+            )?)?
+        } else {
+            let coin_id = vault.coin.coin_id();
+            let challenge = passkey_challenge(delegated_puzzle_hash, coin_id, genesis_challenge);
+            ctx.alloc(&passkey.sign(challenge)?.member_solution(
+                genesis_challenge,
+                delegated_puzzle_hash,
+                coin_id,
+            )?)?
+        };
+
+        let spend = custody.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(custody_hash, solution)]),
+        )?;
+        vault.spend(ctx, &spend)?;
+
+        sim.spend_coins(ctx.take(), &[])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_vault() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let mut allocator = Allocator::new();
+
+        let keys = test_k1_keys(2)?;
+        let custody = CustodyConfig::new(CustodyMember::K1 {
+            public_key: keys[0].public_key(),
+            fast_forward: false,
+        });
+        let custody_hash = custody.custody_hash();
+
+        let (sk, pk, _puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        // Include the custody config in the launcher memos
+        let launcher = Launcher::new(coin.coin_id(), 1);
+        let launcher_coin = launcher.coin();
+        let (mint_vault, vault) =
+            launcher.mint_parsable_vault(ctx, custody_hash, custody.clone())?;
+        p2.spend(ctx, coin, mint_vault)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let solution = sim
+            .solution(launcher_coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut allocator)?;
+
+        let (parsed_vault, memos) =
+            Vault::parse_launcher::<CustodyConfig>(&allocator, launcher_coin, solution)?
+                .expect("could not parse launcher");
+
+        assert_eq!(parsed_vault, vault);
+        assert_eq!(memos, custody);
+
+        // Rotate the custody to a new key
+        let new_custody = CustodyConfig::new(CustodyMember::K1 {
+            public_key: keys[1].public_key(),
+            fast_forward: false,
+        });
+
+        let conditions = Conditions::new().create_coin(new_custody.custody_hash().into(), 1, None);
+        let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
+
+        let solution = k1_solution(ctx, &vault, spend.delegated, &keys[0])?;
+        custody.plan_spend(ctx, &mut spend, &HashMap::from([(custody_hash, solution)]))?;
+
+        vault.spend(ctx, &spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        let puzzle_reveal = sim
+            .puzzle_reveal(vault.coin.coin_id())
+            .expect("missing puzzle")
+            .to_clvm(&mut allocator)?;
+
+        let solution = sim
+            .solution(vault.coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut allocator)?;
+
+        let puzzle = Puzzle::parse(&allocator, puzzle_reveal);
+
+        let child = Vault::parse_child(&mut allocator, vault.coin, puzzle, solution)?
+            .expect("could not parse vault");
+
+        assert_eq!(child, vault.child(new_custody.custody_hash()));
+        assert!(sim.coin_state(child.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[rstest]
+    fn test_parse_melted_vault(#[values(true, false)] nil_puzzle_hash: bool) -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let mut allocator = Allocator::new();
+
+        let k1 = test_k1_key()?;
+        let custody = k1_member(&k1);
+        let custody_hash = custody.custody_hash();
+
+        let vault = mint_vault(&mut sim, ctx, custody_hash)?;
+
+        let melt = if nil_puzzle_hash {
+            Condition::MeltSingleton(MeltSingleton::default())
+        } else {
+            Condition::Other(ctx.alloc(&clvm_list!(51, Bytes32::default(), -113))?)
+        };
+
+        let conditions = Conditions::new().with(melt);
+        let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
+
+        let solution = k1_solution(ctx, &vault, spend.delegated, &k1)?;
+        custody.plan_spend(ctx, &mut spend, &HashMap::from([(custody_hash, solution)]))?;
+
+        vault.spend(ctx, &spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        let puzzle_reveal = sim
+            .puzzle_reveal(vault.coin.coin_id())
+            .expect("missing puzzle")
+            .to_clvm(&mut allocator)?;
+
+        let solution = sim
+            .solution(vault.coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut allocator)?;
+
+        let puzzle = Puzzle::parse(&allocator, puzzle_reveal);
+
+        assert_eq!(
+            Vault::parse_child(&mut allocator, vault.coin, puzzle, solution)?,
+            None
+        );
+
+        Ok(())
+    }
 }
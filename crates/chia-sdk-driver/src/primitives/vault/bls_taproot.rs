@@ -0,0 +1,204 @@
+use chia_bls::PublicKey;
+use chia_protocol::Bytes32;
+use chia_puzzles::DeriveSynthetic;
+use chia_sdk_types::{AggSig, AggSigKind, BlsTaprootMember, BlsTaprootMemberSolution, Mod};
+use clvm_utils::TreeHash;
+
+use crate::{DriverError, Spend, SpendContext};
+
+use super::{member_puzzle_hash, MemberSpend, Restriction};
+
+/// A BLS member whose synthetic key commits to a hidden puzzle, in the same way as the
+/// standard transaction. It can be spent with a signature from the synthetic key (the key path),
+/// or without a signature if the delegated puzzle is the hidden puzzle (the hidden puzzle path).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlsTaproot {
+    pub public_key: PublicKey,
+    pub hidden_puzzle_hash: TreeHash,
+}
+
+impl BlsTaproot {
+    pub fn new(public_key: PublicKey, hidden_puzzle_hash: TreeHash) -> Self {
+        Self {
+            public_key,
+            hidden_puzzle_hash,
+        }
+    }
+
+    /// The key which must sign the delegated puzzle hash with `AGG_SIG_ME` when spending
+    /// with the key path. The secret key can be derived in the same way with [`DeriveSynthetic`].
+    pub fn synthetic_key(&self) -> PublicKey {
+        self.public_key
+            .derive_synthetic_hidden(&self.hidden_puzzle_hash.to_bytes())
+    }
+
+    pub fn member(&self) -> BlsTaprootMember {
+        BlsTaprootMember::new(self.synthetic_key())
+    }
+
+    /// The puzzle hash of this member, without its nonce or restrictions.
+    pub fn inner_puzzle_hash(&self) -> TreeHash {
+        self.member().curry_tree_hash()
+    }
+
+    pub fn member_hash(
+        &self,
+        nonce: usize,
+        restrictions: Vec<Restriction>,
+        top_level: bool,
+    ) -> TreeHash {
+        member_puzzle_hash(nonce, restrictions, self.inner_puzzle_hash(), top_level)
+    }
+
+    /// The `AGG_SIG_ME` condition that the member outputs when spending with the key path.
+    /// This can be converted to a `RequiredBlsSignature` for the vault coin, so that the
+    /// synthetic key can be matched up with the key that needs to sign.
+    pub fn key_path_agg_sig(&self, delegated_puzzle_hash: TreeHash) -> AggSig {
+        AggSig::new(
+            AggSigKind::Me,
+            self.synthetic_key(),
+            Bytes32::from(delegated_puzzle_hash).into(),
+        )
+    }
+
+    /// The solution for the key path, which must be signed by the synthetic key.
+    /// See [`BlsTaproot::key_path_agg_sig`].
+    pub fn key_path_solution() -> BlsTaprootMemberSolution {
+        BlsTaprootMemberSolution::new(None)
+    }
+
+    /// The solution for the hidden puzzle path. The delegated puzzle of the vault spend
+    /// must be the hidden puzzle, and no signature is required.
+    pub fn hidden_path_solution(&self) -> BlsTaprootMemberSolution {
+        BlsTaprootMemberSolution::new(Some(self.public_key))
+    }
+
+    /// Creates a member spend for the key path.
+    pub fn key_path_spend(
+        &self,
+        ctx: &mut SpendContext,
+        nonce: usize,
+        restrictions: Vec<Restriction>,
+    ) -> Result<MemberSpend, DriverError> {
+        self.member_spend(ctx, nonce, restrictions, Self::key_path_solution())
+    }
+
+    /// Creates a member spend for the hidden puzzle path.
+    pub fn hidden_path_spend(
+        &self,
+        ctx: &mut SpendContext,
+        nonce: usize,
+        restrictions: Vec<Restriction>,
+    ) -> Result<MemberSpend, DriverError> {
+        self.member_spend(ctx, nonce, restrictions, self.hidden_path_solution())
+    }
+
+    fn member_spend(
+        &self,
+        ctx: &mut SpendContext,
+        nonce: usize,
+        restrictions: Vec<Restriction>,
+        solution: BlsTaprootMemberSolution,
+    ) -> Result<MemberSpend, DriverError> {
+        let puzzle = ctx.curry(self.member())?;
+        let solution = ctx.alloc(&solution)?;
+        Ok(MemberSpend::new(
+            nonce,
+            restrictions,
+            Spend::new(puzzle, solution),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_signer::{AggSigConstants, RequiredBlsSignature, RequiredSignature};
+    use chia_sdk_test::{test_secret_key, Simulator, SimulatorError};
+    use chia_sdk_types::{Conditions, TESTNET11_CONSTANTS};
+    use clvm_traits::clvm_quote;
+    use clvmr::NodePtr;
+
+    use crate::{Vault, VaultSpend};
+
+    use super::super::tests::mint_vault;
+
+    use super::*;
+
+    #[test]
+    fn test_bls_taproot_vault() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let pk = sk.public_key();
+
+        // The hidden puzzle sends the vault to a fixed custody hash
+        let fallback_hash = TreeHash::new([1; 32]);
+        let hidden_puzzle = ctx.alloc(&clvm_quote!(Conditions::new().create_coin(
+            fallback_hash.into(),
+            1,
+            None
+        )))?;
+
+        let taproot = BlsTaproot::new(pk, ctx.tree_hash(hidden_puzzle));
+        let custody_hash = taproot.member_hash(0, Vec::new(), true);
+        assert_eq!(
+            custody_hash,
+            Vault::custody_hash(0, Vec::new(), taproot.inner_puzzle_hash())
+        );
+
+        let vault = mint_vault(&mut sim, ctx, custody_hash)?;
+
+        // Spend with the key path, which requires a signature from the synthetic key
+        let conditions = Conditions::new().create_coin(custody_hash.into(), 1, None);
+        let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
+        spend
+            .members
+            .insert(custody_hash, taproot.key_path_spend(ctx, 0, Vec::new())?);
+        vault.spend(ctx, &spend)?;
+
+        let coin_spends = ctx.take();
+
+        // The synthetic key is the one that needs to sign the delegated puzzle hash
+        let constants = AggSigConstants::new(TESTNET11_CONSTANTS.agg_sig_me_additional_data);
+        let expected = RequiredBlsSignature::from_condition(
+            &vault.coin,
+            taproot.key_path_agg_sig(ctx.tree_hash(spend.delegated.puzzle)),
+            &constants,
+        );
+        let required =
+            RequiredSignature::from_coin_spends(&mut ctx.allocator, &coin_spends, &constants)?;
+        assert!(matches!(
+            required.as_slice(),
+            [RequiredSignature::Bls(required)]
+                if required.public_key == taproot.synthetic_key()
+                    && required.message() == expected.message()
+        ));
+
+        assert!(matches!(
+            sim.spend_coins(coin_spends.clone(), &[sk.clone()])
+                .unwrap_err(),
+            SimulatorError::MissingKey
+        ));
+
+        let synthetic_sk = sk.derive_synthetic_hidden(&taproot.hidden_puzzle_hash.to_bytes());
+        assert_eq!(synthetic_sk.public_key(), taproot.synthetic_key());
+        sim.spend_coins(coin_spends, &[synthetic_sk])?;
+
+        // Spend with the hidden puzzle path, which doesn't require a signature
+        let vault = vault.child(custody_hash);
+
+        let mut spend = VaultSpend::new(Spend::new(hidden_puzzle, NodePtr::NIL));
+        spend
+            .members
+            .insert(custody_hash, taproot.hidden_path_spend(ctx, 0, Vec::new())?);
+        vault.spend(ctx, &spend)?;
+
+        sim.spend_coins(ctx.take(), &[])?;
+
+        let child = vault.child(fallback_hash);
+        assert!(sim.coin_state(child.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+}
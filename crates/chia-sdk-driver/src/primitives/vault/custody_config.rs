@@ -0,0 +1,521 @@
+use std::collections::HashMap;
+
+use chia_bls::PublicKey;
+use chia_protocol::Bytes32;
+use chia_sdk_types::{
+    BlsMember, FixedPuzzleMember, Mod, PasskeyMember, PasskeyMemberPuzzleAssert, Secp256k1Member,
+    Secp256k1MemberPuzzleAssert, Secp256r1Member, Secp256r1MemberPuzzleAssert, SingletonMember,
+    Timelock,
+};
+use chia_secp::{K1PublicKey, R1PublicKey};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::TreeHash;
+use clvmr::{
+    serde::{node_from_bytes, node_to_bytes},
+    Allocator, NodePtr,
+};
+
+use crate::{DriverError, Spend, SpendContext};
+
+use super::{
+    member_puzzle_hash, BlsTaproot, MemberSpend, MemberSpendKind, MofN, Restriction, VaultSpend,
+};
+
+/// The full custody configuration of a vault, from which the custody hash and the
+/// member spends can be derived. This is a tree of members, each of which can be
+/// wrapped with a nonce and restrictions.
+#[derive(Debug, Clone, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct CustodyConfig {
+    pub nonce: usize,
+    pub restrictions: Vec<CustodyRestriction>,
+    pub member: CustodyMember,
+}
+
+/// A member of a vault's custody configuration.
+///
+/// The order of these variants is part of the serialized format, so new variants
+/// must only be added to the end.
+#[derive(Debug, Clone, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub enum CustodyMember {
+    Bls {
+        public_key: PublicKey,
+    },
+    K1 {
+        public_key: K1PublicKey,
+        fast_forward: bool,
+    },
+    R1 {
+        public_key: R1PublicKey,
+        fast_forward: bool,
+    },
+    Passkey {
+        genesis_challenge: Bytes32,
+        public_key: R1PublicKey,
+        fast_forward: bool,
+    },
+    Singleton {
+        launcher_id: Bytes32,
+    },
+    Fixed {
+        puzzle_hash: Bytes32,
+    },
+    /// A member whose puzzle isn't known by the SDK. Its [`MemberSpend`] must be
+    /// inserted into the [`VaultSpend`] manually before planning the spend, but its
+    /// timelock restrictions are still spent automatically.
+    Custom {
+        puzzle_hash: Bytes32,
+    },
+    MofN {
+        required: usize,
+        members: Vec<CustodyConfig>,
+    },
+    /// A BLS member whose synthetic key commits to a hidden puzzle. See [`BlsTaproot`].
+    BlsTaproot {
+        public_key: PublicKey,
+        hidden_puzzle_hash: Bytes32,
+    },
+}
+
+/// A restriction on a member of a vault's custody configuration.
+///
+/// The order of these variants is part of the serialized format, so new variants
+/// must only be added to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub enum CustodyRestriction {
+    /// Prevents the member from spending until a number of seconds after the coin was created.
+    Timelock { seconds: u64 },
+    /// A restriction whose puzzle isn't known by the SDK. Its spend must be inserted
+    /// into the [`VaultSpend`] manually.
+    Custom {
+        is_member_condition_validator: bool,
+        puzzle_hash: Bytes32,
+    },
+}
+
+impl CustodyConfig {
+    pub fn new(member: CustodyMember) -> Self {
+        Self {
+            nonce: 0,
+            restrictions: Vec::new(),
+            member,
+        }
+    }
+
+    pub fn m_of_n(required: usize, members: Vec<CustodyConfig>) -> Self {
+        Self::new(CustodyMember::MofN { required, members })
+    }
+
+    #[must_use]
+    pub fn with_nonce(mut self, nonce: usize) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    #[must_use]
+    pub fn with_restriction(mut self, restriction: CustodyRestriction) -> Self {
+        self.restrictions.push(restriction);
+        self
+    }
+
+    /// The custody hash of a vault that uses this configuration.
+    pub fn custody_hash(&self) -> TreeHash {
+        self.member_hash(true)
+    }
+
+    /// The puzzle hash of this member, including its nonce and restrictions.
+    /// Only the root of the custody configuration is top level.
+    pub fn member_hash(&self, top_level: bool) -> TreeHash {
+        member_puzzle_hash(
+            self.nonce,
+            self.restrictions(),
+            self.member.inner_puzzle_hash(),
+            top_level,
+        )
+    }
+
+    pub fn restrictions(&self) -> Vec<Restriction> {
+        self.restrictions
+            .iter()
+            .map(CustodyRestriction::restriction)
+            .collect()
+    }
+
+    /// Lists every member that isn't an m of n group, along with its member hash.
+    /// These hashes are used as the keys when planning a spend.
+    pub fn leaves(&self) -> Vec<(TreeHash, &CustodyMember)> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves, true);
+        leaves
+    }
+
+    /// Creates a [`VaultSpend`] for the given delegated spend. See [`CustodyConfig::plan_spend`].
+    pub fn vault_spend(
+        &self,
+        ctx: &mut SpendContext,
+        delegated_spend: Spend,
+        solutions: &HashMap<TreeHash, NodePtr>,
+    ) -> Result<VaultSpend, DriverError> {
+        let mut vault_spend = VaultSpend::new(delegated_spend);
+        self.plan_spend(ctx, &mut vault_spend, solutions)?;
+        Ok(vault_spend)
+    }
+
+    /// Inserts the member and restriction spends needed to spend a vault with this
+    /// configuration, using the members that have a solution.
+    ///
+    /// The solutions are keyed by the member hashes returned by [`CustodyConfig::leaves`].
+    /// For each m of n group, the first members that can be spent are chosen.
+    pub fn plan_spend(
+        &self,
+        ctx: &mut SpendContext,
+        vault_spend: &mut VaultSpend,
+        solutions: &HashMap<TreeHash, NodePtr>,
+    ) -> Result<(), DriverError> {
+        if self.plan(ctx, vault_spend, solutions, true)? {
+            Ok(())
+        } else {
+            Err(DriverError::MissingSubpathSpend)
+        }
+    }
+
+    /// Serializes the configuration, so that it can be backed up.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DriverError> {
+        let mut allocator = Allocator::new();
+        let ptr = self.to_clvm(&mut allocator)?;
+        Ok(node_to_bytes(&allocator, ptr)?)
+    }
+
+    /// Deserializes a configuration that was serialized with [`CustodyConfig::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DriverError> {
+        let mut allocator = Allocator::new();
+        let ptr = node_from_bytes(&mut allocator, bytes)?;
+        Ok(Self::from_clvm(&allocator, ptr)?)
+    }
+
+    fn collect_leaves<'a>(
+        &'a self,
+        leaves: &mut Vec<(TreeHash, &'a CustodyMember)>,
+        top_level: bool,
+    ) {
+        if let CustodyMember::MofN { members, .. } = &self.member {
+            for member in members {
+                member.collect_leaves(leaves, false);
+            }
+        } else {
+            leaves.push((self.member_hash(top_level), &self.member));
+        }
+    }
+
+    fn plan(
+        &self,
+        ctx: &mut SpendContext,
+        vault_spend: &mut VaultSpend,
+        solutions: &HashMap<TreeHash, NodePtr>,
+        top_level: bool,
+    ) -> Result<bool, DriverError> {
+        let member_hash = self.member_hash(top_level);
+
+        let kind = match &self.member {
+            CustodyMember::Custom { .. } => {
+                if !vault_spend.members.contains_key(&member_hash) {
+                    return Ok(false);
+                }
+                None
+            }
+            CustodyMember::MofN { required, members } => {
+                let mut count = 0;
+
+                for member in members {
+                    if count == *required {
+                        break;
+                    }
+
+                    if member.plan(ctx, vault_spend, solutions, false)? {
+                        count += 1;
+                    }
+                }
+
+                if count < *required {
+                    return Ok(false);
+                }
+
+                let items = members
+                    .iter()
+                    .map(|member| member.member_hash(false))
+                    .collect();
+
+                Some(MemberSpendKind::MofN(MofN::new(*required, items)))
+            }
+            member => {
+                let Some(&solution) = solutions.get(&member_hash) else {
+                    return Ok(false);
+                };
+
+                let puzzle = member.leaf_puzzle(ctx)?;
+                Some(MemberSpendKind::Leaf(Spend::new(puzzle, solution)))
+            }
+        };
+
+        for restriction in &self.restrictions {
+            if let CustodyRestriction::Timelock { seconds } = *restriction {
+                let timelock = Timelock::new(seconds);
+                let puzzle = ctx.curry(timelock)?;
+                vault_spend
+                    .restrictions
+                    .insert(timelock.curry_tree_hash(), Spend::new(puzzle, NodePtr::NIL));
+            }
+        }
+
+        if let Some(kind) = kind {
+            vault_spend.members.insert(
+                member_hash,
+                MemberSpend {
+                    nonce: self.nonce,
+                    restrictions: self.restrictions(),
+                    kind,
+                },
+            );
+        }
+
+        Ok(true)
+    }
+}
+
+impl CustodyMember {
+    /// The puzzle hash of this member, without its nonce or restrictions.
+    pub fn inner_puzzle_hash(&self) -> TreeHash {
+        match self {
+            Self::Bls { public_key } => BlsMember::new(*public_key).curry_tree_hash(),
+            Self::K1 {
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    Secp256k1MemberPuzzleAssert::new(*public_key).curry_tree_hash()
+                } else {
+                    Secp256k1Member::new(*public_key).curry_tree_hash()
+                }
+            }
+            Self::R1 {
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    Secp256r1MemberPuzzleAssert::new(*public_key).curry_tree_hash()
+                } else {
+                    Secp256r1Member::new(*public_key).curry_tree_hash()
+                }
+            }
+            Self::Passkey {
+                genesis_challenge,
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    PasskeyMemberPuzzleAssert::new(*genesis_challenge, *public_key)
+                        .curry_tree_hash()
+                } else {
+                    PasskeyMember::new(*genesis_challenge, *public_key).curry_tree_hash()
+                }
+            }
+            Self::Singleton { launcher_id } => SingletonMember::new(*launcher_id).curry_tree_hash(),
+            Self::Fixed { puzzle_hash } => FixedPuzzleMember::new(*puzzle_hash).curry_tree_hash(),
+            Self::Custom { puzzle_hash } => (*puzzle_hash).into(),
+            Self::MofN { required, members } => MofN::new(
+                *required,
+                members
+                    .iter()
+                    .map(|member| member.member_hash(false))
+                    .collect(),
+            )
+            .inner_puzzle_hash(),
+            Self::BlsTaproot {
+                public_key,
+                hidden_puzzle_hash,
+            } => BlsTaproot::new(*public_key, (*hidden_puzzle_hash).into()).inner_puzzle_hash(),
+        }
+    }
+
+    fn leaf_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        match self {
+            Self::Bls { public_key } => ctx.curry(BlsMember::new(*public_key)),
+            Self::K1 {
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    ctx.curry(Secp256k1MemberPuzzleAssert::new(*public_key))
+                } else {
+                    ctx.curry(Secp256k1Member::new(*public_key))
+                }
+            }
+            Self::R1 {
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    ctx.curry(Secp256r1MemberPuzzleAssert::new(*public_key))
+                } else {
+                    ctx.curry(Secp256r1Member::new(*public_key))
+                }
+            }
+            Self::Passkey {
+                genesis_challenge,
+                public_key,
+                fast_forward,
+            } => {
+                if *fast_forward {
+                    ctx.curry(PasskeyMemberPuzzleAssert::new(
+                        *genesis_challenge,
+                        *public_key,
+                    ))
+                } else {
+                    ctx.curry(PasskeyMember::new(*genesis_challenge, *public_key))
+                }
+            }
+            Self::Singleton { launcher_id } => ctx.curry(SingletonMember::new(*launcher_id)),
+            Self::Fixed { puzzle_hash } => ctx.curry(FixedPuzzleMember::new(*puzzle_hash)),
+            Self::BlsTaproot {
+                public_key,
+                hidden_puzzle_hash,
+            } => ctx.curry(BlsTaproot::new(*public_key, (*hidden_puzzle_hash).into()).member()),
+            Self::Custom { .. } | Self::MofN { .. } => Err(DriverError::MissingSubpathSpend),
+        }
+    }
+}
+
+impl CustodyRestriction {
+    pub fn restriction(&self) -> Restriction {
+        match *self {
+            Self::Timelock { seconds } => Restriction {
+                is_member_condition_validator: true,
+                puzzle_hash: Timelock::new(seconds).curry_tree_hash(),
+            },
+            Self::Custom {
+                is_member_condition_validator,
+                puzzle_hash,
+            } => Restriction {
+                is_member_condition_validator,
+                puzzle_hash: puzzle_hash.into(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_test::{test_k1_keys, test_secret_key, Simulator};
+    use chia_sdk_types::Conditions;
+    use chia_secp::K1SecretKey;
+
+    use crate::Vault;
+
+    use super::super::tests::{k1_solution, mint_vault};
+
+    use super::*;
+
+    fn config(public_key: PublicKey, k1_keys: &[K1PublicKey]) -> CustodyConfig {
+        let mut members = vec![CustodyConfig::new(CustodyMember::Bls { public_key })];
+
+        for &public_key in k1_keys {
+            members.push(CustodyConfig::new(CustodyMember::K1 {
+                public_key,
+                fast_forward: false,
+            }));
+        }
+
+        CustodyConfig::m_of_n(2, members)
+    }
+
+    #[test]
+    fn test_custody_config_hash() -> anyhow::Result<()> {
+        let sk = test_secret_key()?;
+        let k1_keys = test_k1_keys(2)?;
+        let k1_public_keys = k1_keys
+            .iter()
+            .map(K1SecretKey::public_key)
+            .collect::<Vec<_>>();
+
+        let custody = config(sk.public_key(), &k1_public_keys);
+
+        let mut items = vec![member_puzzle_hash(
+            0,
+            Vec::new(),
+            BlsMember::new(sk.public_key()).curry_tree_hash(),
+            false,
+        )];
+
+        for &public_key in &k1_public_keys {
+            items.push(member_puzzle_hash(
+                0,
+                Vec::new(),
+                Secp256k1Member::new(public_key).curry_tree_hash(),
+                false,
+            ));
+        }
+
+        let expected = Vault::custody_hash(0, Vec::new(), MofN::new(2, items).inner_puzzle_hash());
+        assert_eq!(custody.custody_hash(), expected);
+
+        let restricted = custody.with_restriction(CustodyRestriction::Timelock { seconds: 100 });
+        assert_ne!(restricted.custody_hash(), expected);
+
+        let bytes = restricted.to_bytes()?;
+        assert_eq!(CustodyConfig::from_bytes(&bytes)?, restricted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custody_config_spend() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let pk = sk.public_key();
+
+        let k1_keys = test_k1_keys(2)?;
+        let custody = config(
+            pk,
+            &k1_keys
+                .iter()
+                .map(K1SecretKey::public_key)
+                .collect::<Vec<_>>(),
+        );
+        let custody_hash = custody.custody_hash();
+
+        let vault = mint_vault(&mut sim, ctx, custody_hash)?;
+
+        // Spend with the BLS key and the second K1 key
+        let conditions = Conditions::new().create_coin(custody_hash.into(), 1, None);
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+
+        let leaves = custody.leaves();
+        assert_eq!(leaves.len(), 3);
+
+        let mut solutions = HashMap::new();
+        solutions.insert(leaves[0].0, NodePtr::NIL);
+        solutions.insert(
+            leaves[2].0,
+            k1_solution(ctx, &vault, delegated_spend, &k1_keys[1])?,
+        );
+
+        let spend = custody.vault_spend(ctx, delegated_spend, &solutions)?;
+        assert_eq!(spend.members.len(), 3);
+
+        vault.spend(ctx, &spend)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        // A single signer isn't enough
+        solutions.remove(&leaves[0].0);
+        assert!(matches!(
+            custody.vault_spend(ctx, delegated_spend, &solutions),
+            Err(DriverError::MissingSubpathSpend)
+        ));
+
+        Ok(())
+    }
+}
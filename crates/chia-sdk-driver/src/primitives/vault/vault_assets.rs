@@ -0,0 +1,196 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_sdk_types::Conditions;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::Allocator;
+
+use crate::{Cat, CatSpend, DriverError, Nft, P2SingletonMessageLayer, SpendContext};
+
+use super::Vault;
+
+impl Vault {
+    /// The layer that locks assets to this vault.
+    pub fn p2_layer(&self) -> P2SingletonMessageLayer {
+        P2SingletonMessageLayer::new(self.launcher_id)
+    }
+
+    /// The puzzle hash that assets are sent to in order to be held by this vault.
+    pub fn p2_puzzle_hash(&self) -> Bytes32 {
+        self.p2_layer().tree_hash().into()
+    }
+
+    /// Spends an XCH coin held by this vault with the given conditions.
+    ///
+    /// Returns the conditions that must be output by the vault's delegated spend
+    /// to authorize it. The vault must be spent with its current custody hash.
+    pub fn spend_held_coin(
+        &self,
+        ctx: &mut SpendContext,
+        coin: Coin,
+        conditions: Conditions,
+    ) -> Result<Conditions, DriverError> {
+        self.p2_layer()
+            .spend_coin(ctx, coin, self.custody_hash.into(), conditions)
+    }
+
+    /// Spends a ring of CATs held by this vault, each with its own conditions.
+    ///
+    /// Returns the conditions that must be output by the vault's delegated spend
+    /// to authorize them. The vault must be spent with its current custody hash.
+    pub fn spend_held_cats(
+        &self,
+        ctx: &mut SpendContext,
+        cats: &[(Cat, Conditions)],
+    ) -> Result<Conditions, DriverError> {
+        let p2 = self.p2_layer();
+
+        let mut cat_spends = Vec::with_capacity(cats.len());
+        let mut send_messages = Conditions::new();
+
+        for (cat, conditions) in cats {
+            let (inner_spend, send_message) = p2.spend_with_message(
+                ctx,
+                cat.coin.coin_id(),
+                self.custody_hash.into(),
+                conditions.clone(),
+            )?;
+
+            cat_spends.push(CatSpend::new(*cat, inner_spend));
+            send_messages = send_messages.extend(send_message);
+        }
+
+        Cat::spend_all(ctx, &cat_spends)?;
+
+        Ok(send_messages)
+    }
+
+    /// Transfers an NFT held by this vault to a new p2 puzzle hash.
+    ///
+    /// Returns the conditions that must be output by the vault's delegated spend to
+    /// authorize it, along with the child NFT. The vault must be spent with its
+    /// current custody hash.
+    pub fn transfer_held_nft<M>(
+        &self,
+        ctx: &mut SpendContext,
+        nft: &Nft<M>,
+        p2_puzzle_hash: Bytes32,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Nft<M>), DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let memos = ctx.hint(p2_puzzle_hash)?;
+        let conditions = extra_conditions.create_coin(p2_puzzle_hash, nft.coin.amount, Some(memos));
+
+        let (inner_spend, send_message) = self.p2_layer().spend_with_message(
+            ctx,
+            nft.coin.coin_id(),
+            self.custody_hash.into(),
+            conditions,
+        )?;
+
+        nft.spend(ctx, inner_spend)?;
+
+        let metadata = nft.info.metadata.clone();
+
+        Ok((
+            send_message,
+            nft.wrapped_child(p2_puzzle_hash, nft.info.current_owner, metadata),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::{test_k1_key, Simulator};
+
+    use crate::{Launcher, NftMint, StandardLayer, VaultSpend};
+
+    use super::super::tests::{k1_member, k1_solution, mint_vault};
+
+    use super::*;
+
+    #[test]
+    fn test_vault_held_assets() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let k1 = test_k1_key()?;
+        let custody = k1_member(&k1);
+        let custody_hash = custody.custody_hash();
+
+        let vault = mint_vault(&mut sim, ctx, custody_hash)?;
+        let vault_puzzle_hash = vault.p2_puzzle_hash();
+
+        // Send XCH, a CAT and an NFT to the vault
+        let xch = sim.new_coin(vault_puzzle_hash, 500);
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let memos = ctx.hint(vault_puzzle_hash)?;
+        let (issue_cat, cat) = Cat::single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1000,
+            Conditions::new().create_coin(vault_puzzle_hash, 1000, Some(memos)),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        let cat = cat.wrapped_child(vault_puzzle_hash, 1000);
+
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let (mint_nft, nft) = Launcher::new(coin.coin_id(), 1).mint_nft(
+            ctx,
+            NftMint::new(NftMetadata::default(), vault_puzzle_hash, 0, None),
+        )?;
+        p2.spend(ctx, coin, mint_nft)?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        // Move everything out of the vault in a single vault spend
+        let memos = ctx.hint(puzzle_hash)?;
+
+        let mut conditions = Conditions::new().create_coin(custody_hash.into(), 1, None);
+
+        conditions = conditions.extend(vault.spend_held_coin(
+            ctx,
+            xch,
+            Conditions::new().create_coin(puzzle_hash, 500, None),
+        )?);
+
+        conditions = conditions.extend(vault.spend_held_cats(
+            ctx,
+            &[(
+                cat,
+                Conditions::new().create_coin(puzzle_hash, 1000, Some(memos)),
+            )],
+        )?);
+
+        let (send_message, new_nft) =
+            vault.transfer_held_nft(ctx, &nft, puzzle_hash, Conditions::new())?;
+        conditions = conditions.extend(send_message);
+
+        let mut spend = VaultSpend::new(ctx.delegated_spend(conditions)?);
+
+        let solution = k1_solution(ctx, &vault, spend.delegated, &k1)?;
+        custody.plan_spend(ctx, &mut spend, &HashMap::from([(custody_hash, solution)]))?;
+
+        vault.spend(ctx, &spend)?;
+
+        sim.spend_coins(ctx.take(), &[])?;
+
+        assert!(sim
+            .coin_state(Coin::new(xch.coin_id(), puzzle_hash, 500).coin_id())
+            .is_some());
+        assert!(sim
+            .coin_state(cat.wrapped_child(puzzle_hash, 1000).coin.coin_id())
+            .is_some());
+        assert!(sim.coin_state(new_nft.coin.coin_id()).is_some());
+        assert_eq!(new_nft.info.p2_puzzle_hash, puzzle_hash);
+
+        Ok(())
+    }
+}
@@ -1,14 +1,40 @@
-use chia_puzzles::{EveProof, Proof};
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{
+    singleton::{LauncherSolution, SingletonArgs},
+    EveProof, Proof,
+};
 use chia_sdk_types::Conditions;
-use clvm_traits::ToClvm;
+use clvm_traits::{FromClvm, ToClvm};
 use clvm_utils::TreeHash;
-use clvmr::Allocator;
+use clvmr::{Allocator, NodePtr};
 
 use crate::{DriverError, Launcher, SpendContext};
 
 use super::Vault;
 
+/// The key value list of a vault's launcher solution when minted with
+/// [`Launcher::mint_parsable_vault`]. It commits to the custody hash of the eve vault,
+/// so that the vault can be found from the launcher spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct VaultLauncherMemos<M = NodePtr> {
+    pub custody_hash: Bytes32,
+    #[clvm(rest)]
+    pub memos: M,
+}
+
+impl<M> VaultLauncherMemos<M> {
+    pub fn new(custody_hash: TreeHash, memos: M) -> Self {
+        Self {
+            custody_hash: custody_hash.into(),
+            memos,
+        }
+    }
+}
+
 impl Launcher {
+    /// Mints a vault with the given custody hash. The memos are used as the key value list
+    /// of the launcher solution as is.
     pub fn mint_vault<M>(
         self,
         ctx: &mut SpendContext,
@@ -31,4 +57,65 @@ impl Launcher {
         );
         Ok((conditions, vault))
     }
+
+    /// Mints a vault with the given custody hash, which is prepended to the memos in the
+    /// launcher solution. Unlike [`Launcher::mint_vault`], the vault and memos can be read
+    /// back with [`Vault::parse_launcher`].
+    pub fn mint_parsable_vault<M>(
+        self,
+        ctx: &mut SpendContext,
+        custody_hash: TreeHash,
+        memos: M,
+    ) -> Result<(Conditions, Vault), DriverError>
+    where
+        M: ToClvm<Allocator>,
+    {
+        self.mint_vault(
+            ctx,
+            custody_hash,
+            VaultLauncherMemos::new(custody_hash, memos),
+        )
+    }
+}
+
+impl Vault {
+    /// Parses the eve vault and the memos from the spend of a launcher coin.
+    ///
+    /// Returns [`None`] if the launcher wasn't spent by [`Launcher::mint_parsable_vault`],
+    /// or if the memos can't be parsed as the given type.
+    pub fn parse_launcher<M>(
+        allocator: &Allocator,
+        launcher_coin: Coin,
+        launcher_solution: NodePtr,
+    ) -> Result<Option<(Self, M)>, DriverError>
+    where
+        M: FromClvm<Allocator>,
+    {
+        let Ok(solution) =
+            LauncherSolution::<VaultLauncherMemos<M>>::from_clvm(allocator, launcher_solution)
+        else {
+            return Ok(None);
+        };
+
+        let launcher_id = launcher_coin.coin_id();
+        let custody_hash = TreeHash::from(solution.key_value_list.custody_hash);
+
+        let singleton_puzzle_hash = SingletonArgs::curry_tree_hash(launcher_id, custody_hash);
+
+        if solution.singleton_puzzle_hash != Bytes32::from(singleton_puzzle_hash) {
+            return Ok(None);
+        }
+
+        let vault = Vault::new(
+            Coin::new(launcher_id, solution.singleton_puzzle_hash, solution.amount),
+            launcher_id,
+            Proof::Eve(EveProof {
+                parent_parent_coin_info: launcher_coin.parent_coin_info,
+                parent_amount: launcher_coin.amount,
+            }),
+            custody_hash,
+        );
+
+        Ok(Some((vault, solution.key_value_list.memos)))
+    }
 }
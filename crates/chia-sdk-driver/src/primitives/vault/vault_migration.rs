@@ -0,0 +1,538 @@
+use std::{collections::HashMap, mem};
+
+use chia_bls::PublicKey;
+use chia_protocol::{Bytes32, Coin, CoinSpend};
+use chia_sdk_types::Conditions;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::Allocator;
+
+use crate::{
+    calculate_spend_cost, Cat, CatSpend, Did, DriverError, Nft, P2SingletonMessageLayer,
+    SpendContext, SpendWithConditions, StandardLayer, MAX_SPEND_BUNDLE_COST,
+};
+
+/// The assets of a standard wallet which should be moved into a vault.
+#[derive(Debug, Clone)]
+pub struct StandardInventory<M, N> {
+    /// The standard layer of each p2 puzzle hash that the assets are locked to.
+    pub p2: HashMap<Bytes32, StandardLayer>,
+    pub coins: Vec<Coin>,
+    pub cats: Vec<Cat>,
+    pub nfts: Vec<Nft<M>>,
+    pub dids: Vec<Did<N>>,
+}
+
+impl<M, N> StandardInventory<M, N> {
+    pub fn new() -> Self {
+        Self {
+            p2: HashMap::new(),
+            coins: Vec::new(),
+            cats: Vec::new(),
+            nfts: Vec::new(),
+            dids: Vec::new(),
+        }
+    }
+
+    /// Adds a synthetic key, so that assets locked to its p2 puzzle hash can be spent.
+    #[must_use]
+    pub fn with_key(mut self, synthetic_key: PublicKey) -> Self {
+        let p2 = StandardLayer::new(synthetic_key);
+        self.p2.insert(p2.tree_hash().into(), p2);
+        self
+    }
+}
+
+impl<M, N> Default for StandardInventory<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves the assets of a standard wallet into a vault, split into batches that each fit in a
+/// spend bundle.
+///
+/// Everything is sent to the vault's p2 puzzle hash, with a hint so that the vault can find
+/// the CATs, NFTs and DIDs. NFTs keep their owner DID, and DIDs keep their metadata and
+/// recovery list, so they stay attributed after the move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultMigration {
+    pub launcher_id: Bytes32,
+    /// The maximum cost of each batch, which defaults to the cost limit of a single spend bundle.
+    pub max_cost: u64,
+    /// The fee paid by each batch, if any.
+    pub fee: Option<MigrationFee>,
+}
+
+/// The fee paid by each batch of a vault migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationFee {
+    /// A standard coin which isn't migrated. The change from each batch funds the next one.
+    pub coin: Coin,
+    /// The fee paid by each batch.
+    pub amount: u64,
+}
+
+/// The coin spends for one batch of a vault migration, and the assets now held by the vault.
+#[derive(Debug, Clone)]
+pub struct VaultMigrationBatch<M, N> {
+    /// The coin spends. If there is a fee, they must be submitted after those of the previous
+    /// batch, since the change from its fee coin is spent.
+    pub coin_spends: Vec<CoinSpend>,
+    /// The cost of the coin spends, which is calculated for each spend on its own. The sum is
+    /// never less than the cost of the batch as a whole.
+    pub cost: u64,
+    /// The change from the fee coin, which pays the fee of the next batch.
+    pub change: Option<Coin>,
+    pub coins: Vec<Coin>,
+    pub cats: Vec<Cat>,
+    pub nfts: Vec<Nft<M>>,
+    pub dids: Vec<Did<N>>,
+}
+
+impl<M, N> VaultMigrationBatch<M, N> {
+    fn new() -> Self {
+        Self {
+            coin_spends: Vec::new(),
+            cost: 0,
+            change: None,
+            coins: Vec::new(),
+            cats: Vec::new(),
+            nfts: Vec::new(),
+            dids: Vec::new(),
+        }
+    }
+}
+
+impl VaultMigration {
+    pub fn new(launcher_id: Bytes32) -> Self {
+        Self {
+            launcher_id,
+            max_cost: MAX_SPEND_BUNDLE_COST,
+            fee: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_cost(self, max_cost: u64) -> Self {
+        Self { max_cost, ..self }
+    }
+
+    /// Pays a fee with each batch, from a standard coin whose key is in the inventory.
+    /// The coin itself must not be in the inventory, since it isn't migrated.
+    #[must_use]
+    pub fn with_fee(self, coin: Coin, amount: u64) -> Self {
+        Self {
+            fee: Some(MigrationFee { coin, amount }),
+            ..self
+        }
+    }
+
+    /// The puzzle hash that the assets are sent to.
+    pub fn p2_puzzle_hash(&self) -> Bytes32 {
+        P2SingletonMessageLayer::new(self.launcher_id)
+            .tree_hash()
+            .into()
+    }
+
+    /// Spends every asset in the inventory to the vault, and returns the batches.
+    ///
+    /// Each coin is spent on its own, so that batches can be split anywhere. Coin spends
+    /// already in the context are left there, rather than being added to a batch.
+    pub fn migrate<M, N>(
+        &self,
+        ctx: &mut SpendContext,
+        inventory: StandardInventory<M, N>,
+    ) -> Result<Vec<VaultMigrationBatch<M, N>>, DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        N: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let existing = ctx.take();
+        let result = self.migrate_batches(ctx, inventory);
+
+        ctx.take();
+
+        for coin_spend in existing {
+            ctx.insert(coin_spend);
+        }
+
+        result
+    }
+
+    fn migrate_batches<M, N>(
+        &self,
+        ctx: &mut SpendContext,
+        inventory: StandardInventory<M, N>,
+    ) -> Result<Vec<VaultMigrationBatch<M, N>>, DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        N: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let p2_puzzle_hash = self.p2_puzzle_hash();
+
+        let StandardInventory {
+            p2: standard_layers,
+            coins,
+            cats,
+            nfts,
+            dids,
+        } = inventory;
+
+        // The fee coin would be spent twice if it were migrated as well.
+        if let Some(fee) = self.fee {
+            if coins.contains(&fee.coin) {
+                return Err(DriverError::FeeCoinMigrated(fee.coin.coin_id()));
+            }
+        }
+
+        let standard_layer = |p2_puzzle_hash: Bytes32| {
+            standard_layers
+                .get(&p2_puzzle_hash)
+                .copied()
+                .ok_or(DriverError::MissingSyntheticKey(p2_puzzle_hash))
+        };
+
+        let mut batches = Vec::new();
+        let mut batch = VaultMigrationBatch::new();
+        let mut fee_coin = self.fee.map(|fee| fee.coin);
+
+        for coin in coins {
+            let p2 = standard_layer(coin.puzzle_hash)?;
+            p2.spend(
+                ctx,
+                coin,
+                Conditions::new().create_coin(p2_puzzle_hash, coin.amount, None),
+            )?;
+
+            self.add_spends(
+                ctx,
+                &standard_layers,
+                &mut batches,
+                &mut batch,
+                &mut fee_coin,
+            )?
+            .coins
+            .push(Coin::new(coin.coin_id(), p2_puzzle_hash, coin.amount));
+        }
+
+        for cat in cats {
+            let p2 = standard_layer(cat.p2_puzzle_hash)?;
+            let memos = ctx.hint(p2_puzzle_hash)?;
+            let inner_spend = p2.spend_with_conditions(
+                ctx,
+                Conditions::new().create_coin(p2_puzzle_hash, cat.coin.amount, Some(memos)),
+            )?;
+            Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+
+            self.add_spends(
+                ctx,
+                &standard_layers,
+                &mut batches,
+                &mut batch,
+                &mut fee_coin,
+            )?
+            .cats
+            .push(cat.wrapped_child(p2_puzzle_hash, cat.coin.amount));
+        }
+
+        for nft in nfts {
+            let p2 = standard_layer(nft.info.p2_puzzle_hash)?;
+            let nft = nft.transfer(ctx, &p2, p2_puzzle_hash, Conditions::new())?;
+
+            self.add_spends(
+                ctx,
+                &standard_layers,
+                &mut batches,
+                &mut batch,
+                &mut fee_coin,
+            )?
+            .nfts
+            .push(nft);
+        }
+
+        for did in dids {
+            let p2 = standard_layer(did.info.p2_puzzle_hash)?;
+            let did = did.transfer(ctx, &p2, p2_puzzle_hash, Conditions::new())?;
+
+            self.add_spends(
+                ctx,
+                &standard_layers,
+                &mut batches,
+                &mut batch,
+                &mut fee_coin,
+            )?
+            .dids
+            .push(did);
+        }
+
+        if !batch.coin_spends.is_empty() {
+            batches.push(batch);
+        }
+
+        Ok(batches)
+    }
+
+    /// Adds the coin spends in the context to the current batch, starting a new batch first
+    /// if they wouldn't fit. Each batch pays the fee before anything else is added to it.
+    fn add_spends<'a, M, N>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &HashMap<Bytes32, StandardLayer>,
+        batches: &mut Vec<VaultMigrationBatch<M, N>>,
+        batch: &'a mut VaultMigrationBatch<M, N>,
+        fee_coin: &mut Option<Coin>,
+    ) -> Result<&'a mut VaultMigrationBatch<M, N>, DriverError> {
+        let coin_spends = ctx.take();
+        let cost = calculate_spend_cost(&mut ctx.allocator, &coin_spends)?;
+
+        if !batch.coin_spends.is_empty() && batch.cost + cost > self.max_cost {
+            batches.push(mem::replace(batch, VaultMigrationBatch::new()));
+        }
+
+        if batch.coin_spends.is_empty() {
+            self.pay_fee(ctx, p2, batch, fee_coin)?;
+        }
+
+        if batch.cost + cost > self.max_cost {
+            return Err(DriverError::CostExceeded {
+                cost: batch.cost + cost,
+                max_cost: self.max_cost,
+            });
+        }
+
+        batch.coin_spends.extend(coin_spends);
+        batch.cost += cost;
+
+        Ok(batch)
+    }
+
+    /// Spends the fee coin in a new batch, and keeps the change for the next batch.
+    fn pay_fee<M, N>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &HashMap<Bytes32, StandardLayer>,
+        batch: &mut VaultMigrationBatch<M, N>,
+        fee_coin: &mut Option<Coin>,
+    ) -> Result<(), DriverError> {
+        let Some(fee) = self.fee else {
+            return Ok(());
+        };
+
+        let found = fee_coin.map_or(0, |coin| coin.amount);
+
+        let Some(coin) = fee_coin.filter(|coin| coin.amount >= fee.amount) else {
+            return Err(DriverError::InsufficientFunding {
+                found,
+                required: fee.amount,
+            });
+        };
+
+        let standard_layer = p2
+            .get(&coin.puzzle_hash)
+            .ok_or(DriverError::MissingSyntheticKey(coin.puzzle_hash))?;
+
+        let change_amount = coin.amount - fee.amount;
+        let mut conditions = Conditions::new().reserve_fee(fee.amount);
+
+        let change = if change_amount > 0 {
+            conditions = conditions.create_coin(coin.puzzle_hash, change_amount, None);
+            Some(Coin::new(coin.coin_id(), coin.puzzle_hash, change_amount))
+        } else {
+            None
+        };
+
+        standard_layer.spend(ctx, coin, conditions)?;
+
+        let coin_spends = ctx.take();
+        batch.cost += calculate_spend_cost(&mut ctx.allocator, &coin_spends)?;
+        batch.coin_spends.extend(coin_spends);
+        batch.change = change;
+        *fee_coin = change;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::Simulator;
+
+    use crate::{DidOwner, IntermediateLauncher, Launcher, NftMint};
+
+    use super::*;
+
+    #[test]
+    fn test_vault_migration() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+
+        // Create a DID, an NFT owned by the DID, and a CAT
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let mint = NftMint::new(
+            NftMetadata::default(),
+            puzzle_hash,
+            300,
+            Some(DidOwner::from_did_info(&did.info)),
+        );
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let did = did.update(ctx, &p2, mint_nft)?;
+
+        let coin = sim.new_coin(puzzle_hash, 1000);
+        let memos = ctx.hint(puzzle_hash)?;
+        let (issue_cat, cat) = Cat::single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, Some(memos)),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        let cat = cat.wrapped_child(puzzle_hash, 1000);
+
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let did_id = did.info.launcher_id;
+
+        let mut inventory = StandardInventory::new().with_key(pk);
+        inventory.coins.push(sim.new_coin(puzzle_hash, 100));
+        inventory.coins.push(sim.new_coin(puzzle_hash, 200));
+        inventory.cats.push(cat);
+        inventory.nfts.push(nft);
+        inventory.dids.push(did);
+
+        let migration = VaultMigration::new(Bytes32::new([1; 32]));
+        assert_eq!(migration.max_cost, MAX_SPEND_BUNDLE_COST);
+
+        // Everything fits in a single spend bundle
+        let batches = migration.migrate(&mut SpendContext::new(), inventory.clone())?;
+        assert_eq!(batches.len(), 1);
+        let total_cost = batches[0].cost;
+
+        // Lowering the cost limit splits the migration into multiple batches
+        let batches = migration
+            .with_max_cost(total_cost - 1)
+            .migrate(ctx, inventory.clone())?;
+        assert!(batches.len() > 1);
+
+        for batch in batches.iter().cloned() {
+            assert!(batch.cost < total_cost);
+            sim.spend_coins(batch.coin_spends, &[sk.clone()])?;
+        }
+
+        let p2_puzzle_hash = migration.p2_puzzle_hash();
+
+        let coins: Vec<Coin> = batches
+            .iter()
+            .flat_map(|batch| batch.coins.clone())
+            .collect();
+        assert_eq!(coins.len(), 2);
+
+        for coin in coins {
+            assert!(sim.coin_state(coin.coin_id()).is_some());
+        }
+
+        for cat in batches.iter().flat_map(|batch| &batch.cats) {
+            assert_eq!(cat.p2_puzzle_hash, p2_puzzle_hash);
+            assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+        }
+
+        for nft in batches.iter().flat_map(|batch| &batch.nfts) {
+            assert_eq!(nft.info.p2_puzzle_hash, p2_puzzle_hash);
+            assert_eq!(nft.info.current_owner, Some(did_id));
+            assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+        }
+
+        for did in batches.iter().flat_map(|batch| &batch.dids) {
+            assert_eq!(did.info.p2_puzzle_hash, p2_puzzle_hash);
+            assert!(sim.coin_state(did.coin.coin_id()).is_some());
+        }
+
+        // The CAT, NFT and DID are hinted, so the vault can find them
+        assert_eq!(sim.hinted_coins(p2_puzzle_hash).len(), 3);
+
+        // Assets locked to an unknown key can't be migrated
+        let mut inventory = StandardInventory::<(), ()>::new();
+        inventory.coins.push(sim.new_coin(puzzle_hash, 1));
+
+        assert!(matches!(
+            migration.migrate(ctx, inventory),
+            Err(DriverError::MissingSyntheticKey(p2)) if p2 == puzzle_hash
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vault_migration_fee() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, fee_coin) = sim.new_p2(25)?;
+
+        let mut inventory = StandardInventory::<(), ()>::new().with_key(pk);
+
+        for _ in 0..3 {
+            inventory.coins.push(sim.new_coin(puzzle_hash, 100));
+        }
+
+        // Only fits a single coin in each batch, along with the fee
+        let single = VaultMigration::new(Bytes32::new([1; 32]))
+            .with_fee(fee_coin, 10)
+            .migrate(
+                ctx,
+                StandardInventory {
+                    coins: vec![inventory.coins[0]],
+                    ..inventory.clone()
+                },
+            )?;
+        let max_cost = single[0].cost;
+
+        let migration = VaultMigration::new(Bytes32::new([1; 32]))
+            .with_max_cost(max_cost)
+            .with_fee(fee_coin, 10);
+
+        // The fee coin can't be migrated along with the other coins
+        assert!(matches!(
+            migration.migrate(
+                ctx,
+                StandardInventory {
+                    coins: vec![inventory.coins[0], fee_coin],
+                    ..inventory.clone()
+                },
+            ),
+            Err(DriverError::FeeCoinMigrated(coin_id)) if coin_id == fee_coin.coin_id()
+        ));
+
+        // The fee coin can't pay for a third batch
+        assert!(matches!(
+            migration.migrate(ctx, inventory.clone()),
+            Err(DriverError::InsufficientFunding {
+                found: 5,
+                required: 10
+            })
+        ));
+
+        inventory.coins.pop();
+
+        let batches = migration.migrate(ctx, inventory)?;
+        assert_eq!(batches.len(), 2);
+
+        for batch in &batches {
+            sim.spend_coins(batch.coin_spends.clone(), &[sk.clone()])?;
+        }
+
+        let change = batches[1].change.unwrap();
+        assert_eq!(change.amount, 5);
+        assert!(sim.coin_state(change.coin_id()).is_some());
+
+        Ok(())
+    }
+}
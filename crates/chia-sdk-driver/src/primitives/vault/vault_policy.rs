@@ -0,0 +1,391 @@
+use chia_protocol::Bytes32;
+use chia_sdk_types::{
+    Mod, Recovery, SpendRateLimit, Timelock, FORCE_ASSERT_COIN_ANNOUNCEMENT_PUZZLE_HASH,
+    FORCE_COIN_MESSAGE_PUZZLE_HASH,
+};
+use clvm_traits::FromClvm;
+use clvm_utils::TreeHash;
+use clvmr::{run_program, ChiaDialect, NodePtr};
+use thiserror::Error;
+
+use crate::{CurriedPuzzle, DriverError, SpendContext, MAX_SPEND_BUNDLE_COST};
+
+use super::{CustodyConfig, CustodyMember, MemberSpendKind, Restriction, VaultSpend};
+
+/// The reason that a restriction would reject a planned vault spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum RestrictionViolation {
+    #[error("missing spend for restriction {0:?}")]
+    MissingSpend(TreeHash),
+
+    #[error("member must output ASSERT_SECONDS_RELATIVE for {0} seconds")]
+    Timelock(u64),
+
+    #[error("member must send a message to a coin")]
+    ForceCoinMessage,
+
+    #[error("member must assert a coin announcement")]
+    ForceAssertCoinAnnouncement,
+
+    #[error("member can only create the recovery coin, without announcements or messages")]
+    Recovery,
+
+    #[error("member can spend at most {0} mojos per window")]
+    RateLimit(u64),
+
+    #[error("custom restriction {0:?} failed")]
+    Custom(TreeHash),
+}
+
+impl CustodyConfig {
+    /// Lists the restrictions that apply to a planned spend, along with the hash of the
+    /// member that each one is on. Only members that have a spend in the [`VaultSpend`]
+    /// are included, in the same way as when the spend is built.
+    pub fn applied_restrictions(&self, vault_spend: &VaultSpend) -> Vec<(TreeHash, Restriction)> {
+        let mut restrictions = Vec::new();
+        self.collect_restrictions(vault_spend, &mut restrictions, true);
+        restrictions
+    }
+
+    /// Runs every restriction that applies to a planned spend, so that a spend which would
+    /// be rejected can be caught before it's signed.
+    ///
+    /// Member condition validators are run on the conditions output by the member they're on,
+    /// and delegated puzzle validators are run on the delegated puzzle hash. Signature members
+    /// can't be run until they're signed, so they're treated as if they output no conditions,
+    /// since they only assert their own signature or coin.
+    pub fn check_restrictions(
+        &self,
+        ctx: &mut SpendContext,
+        vault_spend: &VaultSpend,
+    ) -> Result<(), DriverError> {
+        let delegated_puzzle_hash = ctx.tree_hash(vault_spend.delegated.puzzle);
+
+        if self
+            .check(ctx, vault_spend, delegated_puzzle_hash, true)?
+            .is_none()
+        {
+            return Err(DriverError::MissingSubpathSpend);
+        }
+
+        Ok(())
+    }
+
+    fn collect_restrictions(
+        &self,
+        vault_spend: &VaultSpend,
+        restrictions: &mut Vec<(TreeHash, Restriction)>,
+        top_level: bool,
+    ) -> bool {
+        let member_hash = self.member_hash(top_level);
+
+        if !vault_spend.members.contains_key(&member_hash) {
+            return false;
+        }
+
+        if let CustodyMember::MofN { required, members } = &self.member {
+            for member in members {
+                if member.collect_restrictions(vault_spend, restrictions, false) && *required == 1 {
+                    break;
+                }
+            }
+        }
+
+        for restriction in self.restrictions() {
+            restrictions.push((member_hash, restriction));
+        }
+
+        true
+    }
+
+    /// Checks the restrictions of this member and the members it's made up of, and returns
+    /// the conditions it outputs. If the member isn't part of the spend, returns [`None`].
+    fn check(
+        &self,
+        ctx: &mut SpendContext,
+        vault_spend: &VaultSpend,
+        delegated_puzzle_hash: TreeHash,
+        top_level: bool,
+    ) -> Result<Option<Vec<NodePtr>>, DriverError> {
+        let member_hash = self.member_hash(top_level);
+
+        let Some(member_spend) = vault_spend.members.get(&member_hash) else {
+            return Ok(None);
+        };
+
+        let conditions = match (&self.member, &member_spend.kind) {
+            (CustodyMember::MofN { required, members }, _) => {
+                let mut conditions = Vec::new();
+
+                for member in members {
+                    let Some(output) =
+                        member.check(ctx, vault_spend, delegated_puzzle_hash, false)?
+                    else {
+                        continue;
+                    };
+
+                    conditions.extend(output);
+
+                    if *required == 1 {
+                        break;
+                    }
+                }
+
+                conditions
+            }
+            (
+                CustodyMember::Fixed { .. } | CustodyMember::Custom { .. },
+                MemberSpendKind::Leaf(spend),
+            ) => {
+                let solution =
+                    ctx.alloc(&(Bytes32::from(delegated_puzzle_hash), spend.solution))?;
+                let output = ctx.run(spend.puzzle, solution)?;
+                ctx.extract(output)?
+            }
+            _ => Vec::new(),
+        };
+
+        for restriction in self.restrictions() {
+            let Some(restriction_spend) = vault_spend.restrictions.get(&restriction.puzzle_hash)
+            else {
+                return Err(DriverError::RestrictionViolated {
+                    member_hash,
+                    violation: RestrictionViolation::MissingSpend(restriction.puzzle_hash),
+                });
+            };
+
+            let input = if restriction.is_member_condition_validator {
+                ctx.alloc(&conditions)?
+            } else {
+                ctx.alloc(&Bytes32::from(delegated_puzzle_hash))?
+            };
+
+            let solution = ctx.alloc(&(input, restriction_spend.solution))?;
+
+            // A restriction which raises or can't be run within the cost limit of a spend bundle
+            // would reject the spend, so either counts as a violation.
+            if run_program(
+                &mut ctx.allocator,
+                &ChiaDialect::new(0),
+                restriction_spend.puzzle,
+                solution,
+                MAX_SPEND_BUNDLE_COST,
+            )
+            .is_err()
+            {
+                return Err(DriverError::RestrictionViolated {
+                    member_hash,
+                    violation: restriction_violation(ctx, restriction, restriction_spend.puzzle),
+                });
+            }
+        }
+
+        Ok(Some(conditions))
+    }
+}
+
+fn restriction_violation(
+    ctx: &SpendContext,
+    restriction: Restriction,
+    puzzle: NodePtr,
+) -> RestrictionViolation {
+    if restriction.puzzle_hash == FORCE_COIN_MESSAGE_PUZZLE_HASH {
+        return RestrictionViolation::ForceCoinMessage;
+    }
+
+    if restriction.puzzle_hash == FORCE_ASSERT_COIN_ANNOUNCEMENT_PUZZLE_HASH {
+        return RestrictionViolation::ForceAssertCoinAnnouncement;
+    }
+
+    let Some(curried) = CurriedPuzzle::parse(&ctx.allocator, puzzle) else {
+        return RestrictionViolation::Custom(restriction.puzzle_hash);
+    };
+
+    if curried.mod_hash == Timelock::MOD_HASH {
+        if let Ok(timelock) = Timelock::from_clvm(&ctx.allocator, curried.args) {
+            return RestrictionViolation::Timelock(timelock.seconds);
+        }
+    } else if curried.mod_hash == Recovery::MOD_HASH {
+        return RestrictionViolation::Recovery;
+    } else if curried.mod_hash == SpendRateLimit::MOD_HASH {
+        if let Ok(rate_limit) = SpendRateLimit::from_clvm(&ctx.allocator, curried.args) {
+            return RestrictionViolation::RateLimit(rate_limit.limit);
+        }
+    }
+
+    RestrictionViolation::Custom(restriction.puzzle_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chia_sdk_test::test_k1_keys;
+    use chia_sdk_types::Conditions;
+    use clvm_traits::clvm_quote;
+
+    use crate::{CustodyRestriction, MemberSpend, Spend};
+
+    use super::*;
+
+    #[test]
+    fn test_check_timelock_restriction() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let restriction = CustodyRestriction::Timelock { seconds: 100 };
+        let delegated_spend = ctx.delegated_spend(Conditions::new())?;
+
+        for (conditions, expected) in [
+            (Conditions::new(), false),
+            (Conditions::new().assert_seconds_relative(50), false),
+            (Conditions::new().assert_seconds_relative(100), true),
+        ] {
+            let puzzle = ctx.alloc(&clvm_quote!(conditions))?;
+            let config = CustodyConfig::new(CustodyMember::Custom {
+                puzzle_hash: ctx.tree_hash(puzzle).into(),
+            })
+            .with_restriction(restriction);
+
+            let mut vault_spend = VaultSpend::new(delegated_spend);
+            vault_spend.members.insert(
+                config.custody_hash(),
+                MemberSpend::new(0, config.restrictions(), Spend::new(puzzle, NodePtr::NIL)),
+            );
+            config.plan_spend(ctx, &mut vault_spend, &HashMap::new())?;
+
+            let applied = config.applied_restrictions(&vault_spend);
+            assert_eq!(applied.len(), 1);
+            assert_eq!(applied[0].0, config.custody_hash());
+
+            let result = config.check_restrictions(ctx, &vault_spend);
+
+            if expected {
+                result?;
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(DriverError::RestrictionViolated {
+                        violation: RestrictionViolation::Timelock(100),
+                        ..
+                    })
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_nested_restrictions() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let k1 = test_k1_keys(2)?;
+        let force_coin_message = CustodyRestriction::Custom {
+            is_member_condition_validator: true,
+            puzzle_hash: FORCE_COIN_MESSAGE_PUZZLE_HASH.into(),
+        };
+
+        let config = CustodyConfig::m_of_n(
+            1,
+            vec![
+                CustodyConfig::new(CustodyMember::K1 {
+                    public_key: k1[0].public_key(),
+                    fast_forward: false,
+                })
+                .with_restriction(force_coin_message),
+                CustodyConfig::new(CustodyMember::K1 {
+                    public_key: k1[1].public_key(),
+                    fast_forward: false,
+                }),
+            ],
+        );
+        let leaves: Vec<TreeHash> = config.leaves().into_iter().map(|(hash, _)| hash).collect();
+
+        let delegated_spend = ctx.delegated_spend(Conditions::new())?;
+
+        // The restriction doesn't apply if the unrestricted member is used
+        let vault_spend = config.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(leaves[1], NodePtr::NIL)]),
+        )?;
+        assert!(config.applied_restrictions(&vault_spend).is_empty());
+        config.check_restrictions(ctx, &vault_spend)?;
+
+        // Signature members don't send messages, so the restriction can't be satisfied
+        let mut vault_spend = config.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(leaves[0], NodePtr::NIL)]),
+        )?;
+        assert_eq!(config.applied_restrictions(&vault_spend).len(), 1);
+
+        assert!(matches!(
+            config.check_restrictions(ctx, &vault_spend),
+            Err(DriverError::RestrictionViolated {
+                violation: RestrictionViolation::MissingSpend(puzzle_hash),
+                ..
+            }) if puzzle_hash == FORCE_COIN_MESSAGE_PUZZLE_HASH
+        ));
+
+        let puzzle = ctx.force_coin_message_puzzle()?;
+        vault_spend.restrictions.insert(
+            FORCE_COIN_MESSAGE_PUZZLE_HASH,
+            Spend::new(puzzle, NodePtr::NIL),
+        );
+
+        assert!(matches!(
+            config.check_restrictions(ctx, &vault_spend),
+            Err(DriverError::RestrictionViolated {
+                member_hash,
+                violation: RestrictionViolation::ForceCoinMessage,
+            }) if member_hash == leaves[0]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_expensive_restriction() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        // Adds up more public keys than can be derived within the cost limit of a spend bundle
+        let pubkey_for_exp = ctx.alloc(&(30, (clvm_quote!(1), ())))?;
+        let puzzle = ctx.alloc(&(29, vec![pubkey_for_exp; 5000]))?;
+
+        let restriction = CustodyRestriction::Custom {
+            is_member_condition_validator: false,
+            puzzle_hash: ctx.tree_hash(puzzle).into(),
+        };
+
+        let member_puzzle = ctx.alloc(&clvm_quote!(()))?;
+        let config = CustodyConfig::new(CustodyMember::Custom {
+            puzzle_hash: ctx.tree_hash(member_puzzle).into(),
+        })
+        .with_restriction(restriction);
+
+        let delegated_spend = ctx.delegated_spend(Conditions::new())?;
+        let mut vault_spend = VaultSpend::new(delegated_spend);
+        vault_spend.members.insert(
+            config.custody_hash(),
+            MemberSpend::new(
+                0,
+                config.restrictions(),
+                Spend::new(member_puzzle, NodePtr::NIL),
+            ),
+        );
+        vault_spend
+            .restrictions
+            .insert(ctx.tree_hash(puzzle), Spend::new(puzzle, NodePtr::NIL));
+
+        assert!(matches!(
+            config.check_restrictions(ctx, &vault_spend),
+            Err(DriverError::RestrictionViolated {
+                violation: RestrictionViolation::Custom(puzzle_hash),
+                ..
+            }) if puzzle_hash == ctx.tree_hash(puzzle)
+        ));
+
+        Ok(())
+    }
+}
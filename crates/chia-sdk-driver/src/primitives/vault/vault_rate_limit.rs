@@ -0,0 +1,288 @@
+use chia_protocol::Bytes32;
+use chia_sdk_types::{Conditions, Mod, SpendRateLimit, SpendRateLimitSolution};
+
+use crate::{DriverError, Spend, SpendContext};
+
+use super::{CustodyRestriction, Vault, VaultSpend};
+
+/// A spending limit for a member of a vault, which can spend at most `limit` mojos
+/// every `window` seconds. See [`SpendRateLimit`] for the rules that the puzzle enforces.
+///
+/// Other members of the custody configuration aren't limited, so for example a 1 of 2
+/// between a rate limited key and an m of n can still spend freely with the m of n.
+///
+/// The window restarts whenever the vault coin is recreated, so [`VaultRateLimit::restart_window`]
+/// should be called after every spend of the vault, including those by other members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultRateLimit {
+    pub launcher_id: Bytes32,
+    pub limit: u64,
+    pub window: u64,
+    /// The timestamp at which the current vault coin was created.
+    pub window_start: u64,
+}
+
+impl VaultRateLimit {
+    pub fn new(launcher_id: Bytes32, limit: u64, window: u64, window_start: u64) -> Self {
+        Self {
+            launcher_id,
+            limit,
+            window,
+            window_start,
+        }
+    }
+
+    pub fn restriction(&self) -> SpendRateLimit {
+        SpendRateLimit::new(self.launcher_id, self.limit, self.window)
+    }
+
+    /// The restriction to add to the member that should be rate limited.
+    pub fn custody_restriction(&self) -> CustodyRestriction {
+        CustodyRestriction::Custom {
+            is_member_condition_validator: false,
+            puzzle_hash: self.restriction().curry_tree_hash().into(),
+        }
+    }
+
+    /// The earliest timestamp at which the rate limited member can spend the vault.
+    pub fn window_end(&self) -> u64 {
+        self.window_start.saturating_add(self.window)
+    }
+
+    /// The number of mojos that the rate limited member can spend at the given timestamp.
+    pub fn available(&self, timestamp: u64) -> u64 {
+        if timestamp >= self.window_end() {
+            self.limit
+        } else {
+            0
+        }
+    }
+
+    /// Starts a new window once the vault has been spent, since the coin was recreated.
+    pub fn restart_window(&mut self, timestamp: u64) {
+        self.window_start = timestamp;
+    }
+
+    /// The conditions that the rate limited member must output to spend `amount` mojos
+    /// from the vault. The payments themselves, along with any fee, should be added to these.
+    /// The delegated puzzle must quote these conditions, as [`SpendContext::delegated_spend`] does.
+    ///
+    /// The vault is recreated with the remaining amount, so `amount` must be even.
+    pub fn conditions(&self, vault: &Vault, amount: u64) -> Result<Conditions, DriverError> {
+        if amount % 2 == 1 {
+            return Err(DriverError::OddRateLimitAmount(amount));
+        }
+
+        // The vault must be recreated with at least one mojo
+        let limit = self.limit.min(vault.coin.amount.saturating_sub(1));
+
+        if amount > limit {
+            return Err(DriverError::RateLimitExceeded { amount, limit });
+        }
+
+        Ok(Conditions::new()
+            .assert_my_amount(vault.coin.amount)
+            .assert_my_puzzle_hash(vault.coin.puzzle_hash)
+            .assert_seconds_relative(self.window)
+            .create_coin(vault.custody_hash.into(), vault.coin.amount - amount, None))
+    }
+
+    /// Inserts the restriction spend into the [`VaultSpend`], which reveals the delegated puzzle
+    /// so that the puzzle can check its conditions.
+    pub fn spend(
+        &self,
+        ctx: &mut SpendContext,
+        vault_spend: &mut VaultSpend,
+    ) -> Result<(), DriverError> {
+        let restriction = self.restriction();
+        let puzzle = ctx.curry(restriction)?;
+        let solution = ctx.alloc(&SpendRateLimitSolution::new(vault_spend.delegated.puzzle))?;
+
+        vault_spend
+            .restrictions
+            .insert(restriction.curry_tree_hash(), Spend::new(puzzle, solution));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chia_bls::Signature;
+    use chia_consensus::gen::validation_error::ErrorCode;
+    use chia_protocol::SpendBundle;
+    use chia_sdk_test::{test_k1_keys, Simulator, SimulatorError};
+    use chia_secp::K1SecretKey;
+    use clvmr::NodePtr;
+
+    use crate::{CustodyConfig, CustodyMember, Launcher, RestrictionViolation};
+
+    use super::super::tests::{k1_member, k1_solution, mint_vault_from};
+
+    use super::*;
+
+    fn rate_limited_spend(
+        ctx: &mut SpendContext,
+        custody: &CustodyConfig,
+        rate_limit: &VaultRateLimit,
+        vault: &Vault,
+        conditions: Conditions,
+        key: &K1SecretKey,
+    ) -> anyhow::Result<VaultSpend> {
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+        let solution = k1_solution(ctx, vault, delegated_spend, key)?;
+
+        let mut vault_spend = custody.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(custody.leaves()[0].0, solution)]),
+        )?;
+        rate_limit.spend(ctx, &mut vault_spend)?;
+
+        Ok(vault_spend)
+    }
+
+    fn assert_rejected(sim: &mut Simulator, ctx: &mut SpendContext) {
+        // Skip signing, since the required signatures can't be computed for a failing spend
+        let spend_bundle = SpendBundle::new(ctx.take(), Signature::default());
+
+        assert!(matches!(
+            sim.new_transaction(spend_bundle).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::GeneratorRuntimeError)
+        ));
+    }
+
+    #[test]
+    fn test_vault_rate_limit() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, _puzzle_hash, coin) = sim.new_p2(1001)?;
+        let key = test_k1_keys(1)?.remove(0);
+        let recipient = Bytes32::new([1; 32]);
+
+        let launcher = Launcher::new(coin.coin_id(), 1001);
+        let mut rate_limit = VaultRateLimit::new(launcher.coin().coin_id(), 100, 100, 0);
+
+        let custody = CustodyConfig::m_of_n(
+            1,
+            vec![
+                k1_member(&key).with_restriction(rate_limit.custody_restriction()),
+                CustodyConfig::new(CustodyMember::Bls { public_key: pk }),
+            ],
+        );
+        let custody_hash = custody.custody_hash();
+
+        let vault = mint_vault_from(&mut sim, ctx, coin, launcher, custody_hash)?;
+
+        rate_limit.restart_window(sim.timestamp());
+        assert_eq!(rate_limit.available(sim.timestamp()), 0);
+
+        // The rate limited key can't spend more than the limit
+        assert!(matches!(
+            rate_limit.conditions(&vault, 200),
+            Err(DriverError::RateLimitExceeded {
+                amount: 200,
+                limit: 100
+            })
+        ));
+
+        let conditions = Conditions::new()
+            .assert_my_amount(vault.coin.amount)
+            .assert_my_puzzle_hash(vault.coin.puzzle_hash)
+            .assert_seconds_relative(rate_limit.window)
+            .create_coin(custody_hash.into(), 801, None)
+            .create_coin(recipient, 200, None);
+        let vault_spend = rate_limited_spend(ctx, &custody, &rate_limit, &vault, conditions, &key)?;
+
+        assert!(matches!(
+            custody.check_restrictions(ctx, &vault_spend),
+            Err(DriverError::RestrictionViolated {
+                violation: RestrictionViolation::RateLimit(100),
+                ..
+            })
+        ));
+
+        vault.spend(ctx, &vault_spend)?;
+        assert_rejected(&mut sim, ctx);
+
+        // Odd amounts would recreate the vault with an even amount
+        assert!(matches!(
+            rate_limit.conditions(&vault, 99),
+            Err(DriverError::OddRateLimitAmount(99))
+        ));
+
+        // The delegated puzzle can't get its conditions from the delegated solution, since the
+        // restriction can't see the solution that the vault is spent with
+        let delegated_puzzle = ctx.alloc(&1)?;
+        let delegated_solution = ctx.alloc(
+            &Conditions::new()
+                .create_coin(custody_hash.into(), 1, None)
+                .create_coin(recipient, 1000, None),
+        )?;
+        let delegated_spend = Spend::new(delegated_puzzle, delegated_solution);
+        let solution = k1_solution(ctx, &vault, delegated_spend, &key)?;
+        let mut vault_spend = custody.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(custody.leaves()[0].0, solution)]),
+        )?;
+        rate_limit.spend(ctx, &mut vault_spend)?;
+
+        assert!(matches!(
+            custody.check_restrictions(ctx, &vault_spend),
+            Err(DriverError::RestrictionViolated {
+                violation: RestrictionViolation::RateLimit(100),
+                ..
+            })
+        ));
+
+        vault.spend(ctx, &vault_spend)?;
+        assert_rejected(&mut sim, ctx);
+
+        // Spending within the limit has to wait until the window has passed
+        let conditions = rate_limit
+            .conditions(&vault, 100)?
+            .create_coin(recipient, 100, None);
+        let vault_spend = rate_limited_spend(ctx, &custody, &rate_limit, &vault, conditions, &key)?;
+        custody.check_restrictions(ctx, &vault_spend)?;
+        vault.spend(ctx, &vault_spend)?;
+
+        let coin_spends = ctx.take();
+
+        assert!(matches!(
+            sim.spend_coins(coin_spends.clone(), &[]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::AssertSecondsRelativeFailed)
+        ));
+
+        sim.pass_time(100);
+        assert_eq!(rate_limit.available(sim.timestamp()), 100);
+        sim.spend_coins(coin_spends, &[])?;
+
+        let vault = vault.child_with_amount(custody_hash, 901);
+        assert!(sim.coin_state(vault.coin.coin_id()).is_some());
+
+        rate_limit.restart_window(sim.timestamp());
+        assert_eq!(rate_limit.available(sim.timestamp()), 0);
+
+        // The BLS key isn't limited, so it can spend the rest right away
+        let conditions = Conditions::new()
+            .create_coin(custody_hash.into(), 1, None)
+            .create_coin(recipient, 900, None);
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+        let vault_spend = custody.vault_spend(
+            ctx,
+            delegated_spend,
+            &HashMap::from([(custody.leaves()[1].0, NodePtr::NIL)]),
+        )?;
+        vault.spend(ctx, &vault_spend)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let vault = vault.child_with_amount(custody_hash, 1);
+        assert!(sim.coin_state(vault.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+}
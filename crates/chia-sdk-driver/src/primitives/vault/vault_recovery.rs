@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use chia_sdk_types::{Conditions, Mod, Recovery, RecoverySolution, Timelock};
+use clvm_traits::{clvm_quote, ToClvm};
+use clvm_utils::{tree_hash, tree_hash_atom, tree_hash_pair, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{DriverError, Spend, SpendContext};
+
+use super::{CustodyConfig, CustodyMember, CustodyRestriction, MemberSpend, Vault, VaultSpend};
+
+/// A vault custody configuration that can be rekeyed by a recovery member.
+///
+/// The custody is a 1 of 2 between the primary member and the recovery member. The recovery
+/// member can only move the vault into a [`PendingRecovery`], which either finishes after the
+/// timelock has passed, or is cancelled by the primary member in the meantime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultRecovery {
+    pub nonce: usize,
+    pub primary: CustodyConfig,
+    pub recovery: CustodyConfig,
+    pub timelock: u64,
+}
+
+impl VaultRecovery {
+    pub fn new(primary: CustodyConfig, recovery: CustodyConfig, timelock: u64) -> Self {
+        Self {
+            nonce: 0,
+            primary,
+            recovery,
+            timelock,
+        }
+    }
+
+    #[must_use]
+    pub fn with_nonce(mut self, nonce: usize) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// The recovery restriction, which ensures that the recovery member can only
+    /// replace itself with a timelocked member alongside the primary member.
+    pub fn restriction(&self) -> Recovery {
+        let member_validator_list_hash = tree_hash_pair(
+            Timelock::new(self.timelock).curry_tree_hash(),
+            tree_hash_atom(&[]),
+        );
+
+        Recovery::new(
+            self.primary.member_hash(false).into(),
+            self.nonce,
+            member_validator_list_hash.into(),
+            tree_hash_atom(&[]).into(),
+        )
+    }
+
+    /// The recovery member, including the recovery restriction.
+    pub fn recovery_member(&self) -> CustodyConfig {
+        self.recovery
+            .clone()
+            .with_restriction(CustodyRestriction::Custom {
+                is_member_condition_validator: true,
+                puzzle_hash: self.restriction().curry_tree_hash().into(),
+            })
+    }
+
+    pub fn custody(&self) -> CustodyConfig {
+        CustodyConfig::m_of_n(1, vec![self.primary.clone(), self.recovery_member()])
+            .with_nonce(self.nonce)
+    }
+
+    pub fn custody_hash(&self) -> TreeHash {
+        self.custody().custody_hash()
+    }
+}
+
+/// The state of a vault after a recovery has been initiated.
+///
+/// Its custody is a 1 of 2 between the primary member, which can cancel the recovery,
+/// and a timelocked member that finishes it by rekeying the vault to the new custody hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRecovery {
+    pub recovery: VaultRecovery,
+    pub new_custody_hash: TreeHash,
+    pub amount: u64,
+}
+
+impl PendingRecovery {
+    pub fn new(recovery: VaultRecovery, new_custody_hash: TreeHash, amount: u64) -> Self {
+        Self {
+            recovery,
+            new_custody_hash,
+            amount,
+        }
+    }
+
+    /// The conditions output by the member that finishes the recovery.
+    pub fn finish_conditions(&self) -> Conditions {
+        Conditions::new()
+            .create_coin(self.new_custody_hash.into(), self.amount, None)
+            .assert_seconds_relative(self.recovery.timelock)
+    }
+
+    pub fn finish_puzzle_hash(&self) -> Result<TreeHash, DriverError> {
+        let mut allocator = Allocator::new();
+        let puzzle = clvm_quote!(self.finish_conditions()).to_clvm(&mut allocator)?;
+        Ok(tree_hash(&allocator, puzzle))
+    }
+
+    /// The member that finishes the recovery, which is restricted by the timelock.
+    pub fn finish_member(&self) -> Result<CustodyConfig, DriverError> {
+        Ok(CustodyConfig::new(CustodyMember::Custom {
+            puzzle_hash: self.finish_puzzle_hash()?.into(),
+        })
+        .with_nonce(self.recovery.nonce)
+        .with_restriction(CustodyRestriction::Timelock {
+            seconds: self.recovery.timelock,
+        }))
+    }
+
+    pub fn custody(&self) -> Result<CustodyConfig, DriverError> {
+        Ok(CustodyConfig::m_of_n(
+            1,
+            vec![self.recovery.primary.clone(), self.finish_member()?],
+        )
+        .with_nonce(self.recovery.nonce))
+    }
+
+    pub fn custody_hash(&self) -> Result<TreeHash, DriverError> {
+        Ok(self.custody()?.custody_hash())
+    }
+
+    /// The delegated spend that initiates the recovery.
+    /// It must be authorized by the recovery member.
+    pub fn initiate_spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
+        let custody_hash = self.custody_hash()?;
+        ctx.delegated_spend(Conditions::new().create_coin(custody_hash.into(), self.amount, None))
+    }
+
+    /// The delegated spend that cancels the recovery.
+    /// It must be authorized by the primary member.
+    pub fn cancel_spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
+        let custody_hash = self.recovery.custody_hash();
+        ctx.delegated_spend(Conditions::new().create_coin(custody_hash.into(), self.amount, None))
+    }
+}
+
+impl Vault {
+    /// Spends the vault with the recovery member to initiate a recovery.
+    ///
+    /// The solutions are keyed by member hash, as in [`CustodyConfig::plan_spend`], and must
+    /// authorize the delegated spend returned by [`PendingRecovery::initiate_spend`].
+    pub fn initiate_recovery(
+        &self,
+        ctx: &mut SpendContext,
+        pending: &PendingRecovery,
+        solutions: &HashMap<TreeHash, NodePtr>,
+    ) -> Result<Self, DriverError> {
+        let mut vault_spend = VaultSpend::new(pending.initiate_spend(ctx)?);
+
+        let restriction = pending.recovery.restriction();
+        let puzzle = ctx.curry(restriction)?;
+        let solution = ctx.alloc(&RecoverySolution::new(pending.finish_puzzle_hash()?.into()))?;
+        vault_spend
+            .restrictions
+            .insert(restriction.curry_tree_hash(), Spend::new(puzzle, solution));
+
+        pending
+            .recovery
+            .custody()
+            .plan_spend(ctx, &mut vault_spend, solutions)?;
+
+        self.spend(ctx, &vault_spend)?;
+
+        Ok(self.child(pending.custody_hash()?))
+    }
+
+    /// Finishes a recovery once the timelock has passed, by rekeying the vault
+    /// to the new custody hash. This doesn't require any signatures.
+    pub fn finish_recovery(
+        &self,
+        ctx: &mut SpendContext,
+        pending: &PendingRecovery,
+    ) -> Result<Self, DriverError> {
+        let mut vault_spend = VaultSpend::new(Spend::new(NodePtr::NIL, NodePtr::NIL));
+
+        let finish_member = pending.finish_member()?;
+        let puzzle = ctx.alloc(&clvm_quote!(pending.finish_conditions()))?;
+        vault_spend.members.insert(
+            finish_member.member_hash(false),
+            MemberSpend::new(
+                finish_member.nonce,
+                finish_member.restrictions(),
+                Spend::new(puzzle, NodePtr::NIL),
+            ),
+        );
+
+        pending
+            .custody()?
+            .plan_spend(ctx, &mut vault_spend, &HashMap::new())?;
+
+        self.spend(ctx, &vault_spend)?;
+
+        Ok(self.child(pending.new_custody_hash))
+    }
+
+    /// Spends the vault with the primary member to cancel a recovery,
+    /// which returns it to its original custody.
+    ///
+    /// The solutions are keyed by member hash, as in [`CustodyConfig::plan_spend`], and must
+    /// authorize the delegated spend returned by [`PendingRecovery::cancel_spend`].
+    pub fn cancel_recovery(
+        &self,
+        ctx: &mut SpendContext,
+        pending: &PendingRecovery,
+        solutions: &HashMap<TreeHash, NodePtr>,
+    ) -> Result<Self, DriverError> {
+        let delegated_spend = pending.cancel_spend(ctx)?;
+        let vault_spend = pending
+            .custody()?
+            .vault_spend(ctx, delegated_spend, solutions)?;
+
+        self.spend(ctx, &vault_spend)?;
+
+        Ok(self.child(pending.recovery.custody_hash()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_consensus::gen::validation_error::ErrorCode;
+    use chia_sdk_test::{test_k1_keys, Simulator, SimulatorError};
+
+    use super::super::tests::{k1_member, k1_solution, mint_vault};
+
+    use super::*;
+
+    #[test]
+    fn test_vault_recovery() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let keys = test_k1_keys(3)?;
+        let recovery = VaultRecovery::new(k1_member(&keys[0]), k1_member(&keys[1]), 100);
+        let new_custody = k1_member(&keys[2]);
+
+        let vault = mint_vault(&mut sim, ctx, recovery.custody_hash())?;
+
+        // Initiate the recovery with the recovery key
+        let pending = PendingRecovery::new(
+            recovery.clone(),
+            new_custody.custody_hash(),
+            vault.coin.amount,
+        );
+
+        let delegated_spend = pending.initiate_spend(ctx)?;
+        let member_hash = recovery.recovery_member().member_hash(false);
+        let solution = k1_solution(ctx, &vault, delegated_spend, &keys[1])?;
+        let solutions = HashMap::from([(member_hash, solution)]);
+
+        let vault = vault.initiate_recovery(ctx, &pending, &solutions)?;
+        sim.spend_coins(ctx.take(), &[])?;
+        assert_eq!(vault.custody_hash, pending.custody_hash()?);
+
+        // The recovery can't be finished until the timelock has passed
+        vault.finish_recovery(ctx, &pending)?;
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[]).unwrap_err(),
+            SimulatorError::Validation(ErrorCode::AssertSecondsRelativeFailed)
+        ));
+
+        sim.pass_time(100);
+
+        let vault = vault.finish_recovery(ctx, &pending)?;
+        sim.spend_coins(ctx.take(), &[])?;
+        assert_eq!(vault.custody_hash, new_custody.custody_hash());
+
+        // Make sure the vault is spendable with the new key
+        let delegated_spend = ctx.delegated_spend(Conditions::new().create_coin(
+            vault.custody_hash.into(),
+            vault.coin.amount,
+            None,
+        ))?;
+        let solution = k1_solution(ctx, &vault, delegated_spend, &keys[2])?;
+        let solutions = HashMap::from([(new_custody.custody_hash(), solution)]);
+        let vault_spend = new_custody.vault_spend(ctx, delegated_spend, &solutions)?;
+        vault.spend(ctx, &vault_spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel_vault_recovery() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let keys = test_k1_keys(3)?;
+        let recovery = VaultRecovery::new(k1_member(&keys[0]), k1_member(&keys[1]), 100);
+
+        let vault = mint_vault(&mut sim, ctx, recovery.custody_hash())?;
+
+        let pending = PendingRecovery::new(
+            recovery.clone(),
+            k1_member(&keys[2]).custody_hash(),
+            vault.coin.amount,
+        );
+
+        let delegated_spend = pending.initiate_spend(ctx)?;
+        let member_hash = recovery.recovery_member().member_hash(false);
+        let solution = k1_solution(ctx, &vault, delegated_spend, &keys[1])?;
+        let solutions = HashMap::from([(member_hash, solution)]);
+
+        let vault = vault.initiate_recovery(ctx, &pending, &solutions)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        // Cancel the recovery with the primary key during the timelock
+        let delegated_spend = pending.cancel_spend(ctx)?;
+        let member_hash = recovery.primary.member_hash(false);
+        let solution = k1_solution(ctx, &vault, delegated_spend, &keys[0])?;
+        let solutions = HashMap::from([(member_hash, solution)]);
+
+        let vault = vault.cancel_recovery(ctx, &pending, &solutions)?;
+        sim.spend_coins(ctx.take(), &[])?;
+        assert_eq!(vault.custody_hash, recovery.custody_hash());
+
+        // The cancelled recovery can no longer be finished
+        sim.pass_time(100);
+        assert!(matches!(
+            vault.finish_recovery(ctx, &pending),
+            Err(DriverError::MissingSubpathSpend)
+        ));
+
+        Ok(())
+    }
+}
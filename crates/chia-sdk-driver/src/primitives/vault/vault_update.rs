@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use chia_protocol::Bytes32;
+use chia_sdk_types::Conditions;
+use clvm_traits::ToClvm;
+use clvm_utils::{tree_hash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{DriverError, Spend, SpendContext};
+
+use super::{CustodyConfig, CustodyMember, Vault};
+
+impl CustodyConfig {
+    /// The tree hash of the configuration itself, which is included as a memo when the custody
+    /// of a vault is updated. Unlike the custody hash, this commits to every member and
+    /// restriction, so it can be used to look up the configuration from a backup.
+    pub fn config_hash(&self) -> Result<TreeHash, DriverError> {
+        let mut allocator = Allocator::new();
+        let ptr = self.to_clvm(&mut allocator)?;
+        Ok(tree_hash(&allocator, ptr))
+    }
+
+    /// Checks that a vault with this configuration could be spent, by rejecting any m of n
+    /// group that requires no members, has no members, or requires more members than it has.
+    pub fn validate(&self) -> Result<(), DriverError> {
+        let CustodyMember::MofN { required, members } = &self.member else {
+            return Ok(());
+        };
+
+        if *required == 0 || *required > members.len() {
+            return Err(DriverError::InvalidMofN {
+                required: *required,
+                total: members.len(),
+            });
+        }
+
+        for member in members {
+            member.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Vault {
+    /// The delegated spend that recreates the vault with a new custody configuration.
+    ///
+    /// The new coin is hinted with the launcher id, and the [`CustodyConfig::config_hash`]
+    /// is added as a second memo so that the other members can find the new configuration.
+    pub fn update_custody_spend(
+        &self,
+        ctx: &mut SpendContext,
+        new_config: &CustodyConfig,
+    ) -> Result<Spend, DriverError> {
+        new_config.validate()?;
+
+        let config_hash: Bytes32 = new_config.config_hash()?.into();
+        let memos = ctx.memos(&vec![self.launcher_id, config_hash])?;
+
+        ctx.delegated_spend(Conditions::new().create_coin(
+            new_config.custody_hash().into(),
+            self.coin.amount,
+            Some(memos),
+        ))
+    }
+
+    /// Spends the vault with its current custody configuration to update it to a new one,
+    /// such as when a key is rotated or a member is added.
+    ///
+    /// The solutions are keyed by member hash, as in [`CustodyConfig::plan_spend`], and must
+    /// authorize the delegated spend returned by [`Vault::update_custody_spend`].
+    pub fn update_custody(
+        &self,
+        ctx: &mut SpendContext,
+        custody: &CustodyConfig,
+        new_config: &CustodyConfig,
+        solutions: &HashMap<TreeHash, NodePtr>,
+    ) -> Result<Self, DriverError> {
+        if custody.custody_hash() != self.custody_hash {
+            return Err(DriverError::CustodyMismatch);
+        }
+
+        let delegated_spend = self.update_custody_spend(ctx, new_config)?;
+        let vault_spend = custody.vault_spend(ctx, delegated_spend, solutions)?;
+
+        self.spend(ctx, &vault_spend)?;
+
+        Ok(self.child(new_config.custody_hash()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_test::{test_k1_keys, Simulator};
+    use chia_sdk_types::Condition;
+    use clvm_traits::FromClvm;
+
+    use super::super::tests::{k1_member, k1_solution, mint_vault};
+
+    use super::*;
+
+    #[test]
+    fn test_update_custody() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let keys = test_k1_keys(3)?;
+        let custody = k1_member(&keys[0]);
+
+        let vault = mint_vault(&mut sim, ctx, custody.custody_hash())?;
+
+        // Rotate the key to a 2 of 2 between the other keys
+        let new_config = CustodyConfig::m_of_n(2, vec![k1_member(&keys[1]), k1_member(&keys[2])]);
+
+        let delegated_spend = vault.update_custody_spend(ctx, &new_config)?;
+        let solution = k1_solution(ctx, &vault, delegated_spend, &keys[0])?;
+        let solutions = HashMap::from([(custody.custody_hash(), solution)]);
+
+        // The co-signers can find the new configuration from the memos
+        let output = ctx.run(delegated_spend.puzzle, delegated_spend.solution)?;
+        let conditions = Vec::<Condition>::from_clvm(&ctx.allocator, output)?;
+        let create_coin = conditions
+            .into_iter()
+            .find_map(Condition::into_create_coin)
+            .expect("missing create coin");
+        let memos = Vec::<Bytes32>::from_clvm(&ctx.allocator, create_coin.memos.unwrap().value)?;
+        assert_eq!(
+            memos,
+            vec![vault.launcher_id, new_config.config_hash()?.into()]
+        );
+
+        // The current configuration is needed to authorize the update
+        assert!(matches!(
+            vault.update_custody(ctx, &new_config, &new_config, &solutions),
+            Err(DriverError::CustodyMismatch)
+        ));
+
+        let vault = vault.update_custody(ctx, &custody, &new_config, &solutions)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        assert_eq!(vault.custody_hash, new_config.custody_hash());
+        assert!(sim.coin_state(vault.coin.coin_id()).is_some());
+        assert_eq!(
+            sim.hinted_coins(vault.launcher_id),
+            vec![vault.coin.coin_id()]
+        );
+
+        // Make sure the vault is spendable with both of the new keys
+        let delegated_spend = vault.update_custody_spend(ctx, &custody)?;
+        let leaves: Vec<TreeHash> = new_config.leaves().into_iter().map(|(h, _)| h).collect();
+        let solutions = HashMap::from([
+            (
+                leaves[0],
+                k1_solution(ctx, &vault, delegated_spend, &keys[1])?,
+            ),
+            (
+                leaves[1],
+                k1_solution(ctx, &vault, delegated_spend, &keys[2])?,
+            ),
+        ]);
+
+        let vault = vault.update_custody(ctx, &new_config, &custody, &solutions)?;
+        sim.spend_coins(ctx.take(), &[])?;
+        assert_eq!(vault.custody_hash, custody.custody_hash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_custody() -> anyhow::Result<()> {
+        let keys = test_k1_keys(2)?;
+        let members = vec![k1_member(&keys[0]), k1_member(&keys[1])];
+
+        CustodyConfig::m_of_n(2, members.clone()).validate()?;
+
+        for (required, total, config) in [
+            (3, 2, CustodyConfig::m_of_n(3, members.clone())),
+            (0, 2, CustodyConfig::m_of_n(0, members.clone())),
+            (1, 0, CustodyConfig::m_of_n(1, Vec::new())),
+            (
+                1,
+                0,
+                CustodyConfig::m_of_n(
+                    1,
+                    vec![members[0].clone(), CustodyConfig::m_of_n(1, Vec::new())],
+                ),
+            ),
+        ] {
+            let Err(DriverError::InvalidMofN {
+                required: r,
+                total: t,
+            }) = config.validate()
+            else {
+                panic!("expected an invalid m of n");
+            };
+            assert_eq!((r, t), (required, total));
+        }
+
+        Ok(())
+    }
+}
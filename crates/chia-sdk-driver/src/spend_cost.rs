@@ -0,0 +1,38 @@
+use chia_bls::Signature;
+use chia_consensus::spendbundle_conditions::get_conditions_from_spendbundle;
+use chia_protocol::{CoinSpend, SpendBundle};
+use chia_sdk_types::MAINNET_CONSTANTS;
+use clvmr::Allocator;
+
+use crate::DriverError;
+
+/// The maximum cost of the coin spends in a single block.
+pub const MAX_BLOCK_COST_CLVM: u64 = 11_000_000_000;
+
+/// The maximum cost of a single spend bundle, which is half of the block limit.
+/// Full nodes reject larger spend bundles from the mempool.
+pub const MAX_SPEND_BUNDLE_COST: u64 = MAX_BLOCK_COST_CLVM / 2;
+
+/// Calculates the cost of including the coin spends in a block, the same way that a full node
+/// validates spend bundles in its mempool.
+///
+/// This includes running the puzzles, the conditions they output, and the size of the
+/// puzzle reveals and solutions. The conditions are validated as well, so the coin spends
+/// must be a complete spend bundle, but the signature isn't checked.
+pub fn calculate_spend_cost(
+    allocator: &mut Allocator,
+    coin_spends: &[CoinSpend],
+) -> Result<u64, DriverError> {
+    let spend_bundle = SpendBundle::new(coin_spends.to_vec(), Signature::default());
+
+    let conditions = get_conditions_from_spendbundle(
+        allocator,
+        &spend_bundle,
+        MAX_BLOCK_COST_CLVM,
+        MAINNET_CONSTANTS.hard_fork_height,
+        &MAINNET_CONSTANTS,
+    )
+    .map_err(|error| DriverError::Validation(error.1))?;
+
+    Ok(conditions.cost)
+}
@@ -2,6 +2,8 @@ use clvm_traits::{FromClvmError, ToClvmError};
 use clvmr::reduction::EvalErr;
 use thiserror::Error;
 
+use crate::InvalidSignature;
+
 #[derive(Debug, Error)]
 pub enum SignerError {
     #[error("Eval error: {0}")]
@@ -18,4 +20,23 @@ pub enum SignerError {
 
     #[error("Invalid secp key")]
     InvalidSecpKey(#[from] k256::ecdsa::Error),
+
+    #[error("Invalid signatures: {0:?}")]
+    InvalidSignatures(Vec<InvalidSignature>),
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("Invalid client data JSON: {0}")]
+    InvalidClientDataJson(#[from] serde_json::Error),
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("Client data is not for a WebAuthn assertion")]
+    InvalidClientDataType,
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("Client data doesn't contain the expected passkey challenge")]
+    PasskeyChallengeMismatch,
+
+    #[cfg(feature = "experimental-vaults")]
+    #[error("Invalid passkey signature")]
+    InvalidPasskeySignature,
 }
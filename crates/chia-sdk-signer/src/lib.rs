@@ -2,8 +2,16 @@ mod bls;
 mod error;
 mod required_signature;
 mod secp;
+mod verify;
 
 pub use bls::*;
 pub use error::*;
 pub use required_signature::*;
 pub use secp::*;
+pub use verify::*;
+
+#[cfg(feature = "experimental-vaults")]
+mod passkey;
+
+#[cfg(feature = "experimental-vaults")]
+pub use passkey::*;
@@ -0,0 +1,218 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chia_protocol::{Bytes, Bytes32};
+use chia_sdk_types::{PasskeyMemberPuzzleAssertSolution, PasskeyMemberSolution};
+use chia_secp::{R1PublicKey, R1Signature};
+use clvmr::sha2::Sha256;
+use serde_json::Value;
+
+use crate::SignerError;
+
+/// The `type` of the client data for a `WebAuthn` assertion.
+pub const WEBAUTHN_GET_TYPE: &str = "webauthn.get";
+
+/// Calculates the challenge that a passkey must sign to authorize a vault's delegated puzzle.
+///
+/// The `coin_id` is the id of the vault coin being spent. If the member is fast forwardable,
+/// the puzzle hash of the vault coin is used instead, since the coin id isn't known in advance.
+pub fn passkey_challenge(
+    delegated_puzzle_hash: Bytes32,
+    coin_id: Bytes32,
+    genesis_challenge: Bytes32,
+) -> Bytes32 {
+    let mut hasher = Sha256::new();
+    hasher.update(delegated_puzzle_hash);
+    hasher.update(coin_id);
+    hasher.update(genesis_challenge);
+    hasher.finalize().into()
+}
+
+/// Encodes a challenge as unpadded base64url, which is how it appears in the client data.
+pub fn encode_passkey_challenge(challenge: Bytes32) -> String {
+    URL_SAFE_NO_PAD.encode(challenge)
+}
+
+/// A `WebAuthn` assertion, which is produced by a passkey when it signs a challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasskeyAssertion {
+    pub authenticator_data: Bytes,
+    pub client_data_json: Bytes,
+    pub signature: R1Signature,
+}
+
+impl PasskeyAssertion {
+    pub fn new(authenticator_data: Bytes, client_data_json: Bytes, signature: R1Signature) -> Self {
+        Self {
+            authenticator_data,
+            client_data_json,
+            signature,
+        }
+    }
+
+    /// The message hash that is signed by the passkey, which is the hash of the
+    /// authenticator data followed by the hash of the client data.
+    pub fn message_hash(&self) -> [u8; 32] {
+        passkey_message_hash(&self.authenticator_data, &self.client_data_json)
+    }
+
+    /// Parses the client data and checks that it's an assertion of the given challenge.
+    ///
+    /// Returns the index of the challenge in the client data, which is used by the puzzle
+    /// to find the challenge without parsing the JSON.
+    pub fn challenge_index(&self, challenge: Bytes32) -> Result<usize, SignerError> {
+        let client_data: Value = serde_json::from_slice(&self.client_data_json)?;
+
+        if client_data.get("type").and_then(Value::as_str) != Some(WEBAUTHN_GET_TYPE) {
+            return Err(SignerError::InvalidClientDataType);
+        }
+
+        let encoded = encode_passkey_challenge(challenge);
+
+        if client_data.get("challenge").and_then(Value::as_str) != Some(encoded.as_str()) {
+            return Err(SignerError::PasskeyChallengeMismatch);
+        }
+
+        let needle = format!("\"challenge\":\"{encoded}\"");
+
+        self.client_data_json
+            .windows(needle.len())
+            .position(|window| window == needle.as_bytes())
+            .ok_or(SignerError::PasskeyChallengeMismatch)
+    }
+
+    /// Checks that the assertion is for the given challenge,
+    /// and that it's signed by the public key.
+    pub fn verify(&self, public_key: &R1PublicKey, challenge: Bytes32) -> Result<(), SignerError> {
+        self.challenge_index(challenge)?;
+
+        if public_key.verify_prehashed(&self.message_hash(), &self.signature) {
+            Ok(())
+        } else {
+            Err(SignerError::InvalidPasskeySignature)
+        }
+    }
+
+    /// Creates the solution for a passkey member that authorizes the delegated puzzle.
+    pub fn member_solution(
+        self,
+        genesis_challenge: Bytes32,
+        delegated_puzzle_hash: Bytes32,
+        coin_id: Bytes32,
+    ) -> Result<PasskeyMemberSolution, SignerError> {
+        let challenge = passkey_challenge(delegated_puzzle_hash, coin_id, genesis_challenge);
+        let challenge_index = self.challenge_index(challenge)?;
+
+        Ok(PasskeyMemberSolution {
+            authenticator_data: self.authenticator_data,
+            client_data_json: self.client_data_json,
+            challenge_index,
+            signature: self.signature,
+            coin_id,
+        })
+    }
+
+    /// Creates the solution for a fast forwardable passkey member that authorizes the
+    /// delegated puzzle. The puzzle hash is that of the vault coin being spent.
+    pub fn member_puzzle_assert_solution(
+        self,
+        genesis_challenge: Bytes32,
+        delegated_puzzle_hash: Bytes32,
+        puzzle_hash: Bytes32,
+    ) -> Result<PasskeyMemberPuzzleAssertSolution, SignerError> {
+        let challenge = passkey_challenge(delegated_puzzle_hash, puzzle_hash, genesis_challenge);
+        let challenge_index = self.challenge_index(challenge)?;
+
+        Ok(PasskeyMemberPuzzleAssertSolution {
+            authenticator_data: self.authenticator_data,
+            client_data_json: self.client_data_json,
+            challenge_index,
+            signature: self.signature,
+            puzzle_hash,
+        })
+    }
+}
+
+/// Calculates the message hash that a passkey signs for the given authenticator data
+/// and client data.
+pub fn passkey_message_hash(authenticator_data: &[u8], client_data_json: &[u8]) -> [u8; 32] {
+    let mut client_data_hasher = Sha256::new();
+    client_data_hasher.update(client_data_json);
+
+    let mut hasher = Sha256::new();
+    hasher.update(authenticator_data);
+    hasher.update(client_data_hasher.finalize());
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_secp::R1SecretKey;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn assertion(
+        sk: &R1SecretKey,
+        kind: &str,
+        challenge: &str,
+    ) -> anyhow::Result<PasskeyAssertion> {
+        let authenticator_data = Bytes::new([0x11; 37].to_vec());
+        let client_data_json = Bytes::new(
+            format!(r#"{{"type":"{kind}","challenge":"{challenge}","origin":"http://localhost"}}"#)
+                .into_bytes(),
+        );
+        let message_hash = passkey_message_hash(&authenticator_data, &client_data_json);
+        let signature = sk.sign_prehashed(&message_hash)?;
+        Ok(PasskeyAssertion::new(
+            authenticator_data,
+            client_data_json,
+            signature,
+        ))
+    }
+
+    #[test]
+    fn test_passkey_challenge_encoding() {
+        let challenge = Bytes32::new([0xfb; 32]);
+        let encoded = encode_passkey_challenge(challenge);
+
+        assert_eq!(encoded.len(), 43);
+        assert!(!encoded.contains(['+', '/', '=']));
+    }
+
+    #[test]
+    fn test_verify_passkey_assertion() -> anyhow::Result<()> {
+        let mut rng = ChaCha8Rng::seed_from_u64(1337);
+
+        let sk = R1SecretKey::from_bytes(&rng.gen())?;
+        let other_sk = R1SecretKey::from_bytes(&rng.gen())?;
+
+        let challenge = passkey_challenge(
+            Bytes32::new(rng.gen()),
+            Bytes32::new(rng.gen()),
+            Bytes32::new(rng.gen()),
+        );
+        let encoded = encode_passkey_challenge(challenge);
+
+        let valid = assertion(&sk, WEBAUTHN_GET_TYPE, &encoded)?;
+        valid.verify(&sk.public_key(), challenge)?;
+        assert_eq!(valid.challenge_index(challenge)?, 23);
+
+        assert!(matches!(
+            valid.verify(&other_sk.public_key(), challenge),
+            Err(SignerError::InvalidPasskeySignature)
+        ));
+
+        assert!(matches!(
+            valid.verify(&sk.public_key(), Bytes32::new(rng.gen())),
+            Err(SignerError::PasskeyChallengeMismatch)
+        ));
+
+        let wrong_type = assertion(&sk, "webauthn.create", &encoded)?;
+        assert!(matches!(
+            wrong_type.verify(&sk.public_key(), challenge),
+            Err(SignerError::InvalidClientDataType)
+        ));
+
+        Ok(())
+    }
+}
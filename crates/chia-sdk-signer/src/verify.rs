@@ -0,0 +1,201 @@
+use chia_bls::{aggregate_verify, Signature};
+use chia_protocol::{Bytes32, SpendBundle};
+use chia_secp::{K1Signature, R1Signature};
+use clvm_traits::FromClvm;
+use clvmr::Allocator;
+
+use crate::{
+    AggSigConstants, RequiredBlsSignature, RequiredSecpSignature, RequiredSignature, SecpPublicKey,
+    SignerError,
+};
+
+/// A required signature that is either missing from a spend bundle or doesn't verify.
+#[derive(Debug, Clone)]
+pub enum InvalidSignature {
+    /// The aggregated signature doesn't cover the required BLS signatures.
+    /// Since BLS signatures are aggregated, it's not possible to tell which of them are missing or wrong,
+    /// so every required BLS signature is listed alongside the id of the coin that requires it.
+    Bls(Vec<(Bytes32, RequiredBlsSignature)>),
+    /// The secp signature revealed in the solution of the coin is missing, malformed, or doesn't verify.
+    Secp(Bytes32, RequiredSecpSignature),
+}
+
+/// Runs every coin spend in the spend bundle and verifies the signatures it requires.
+/// This includes both the `AggSig*` conditions covered by the aggregated signature,
+/// and the secp signatures that are passed into the solutions.
+///
+/// If any signature is missing or wrong, [`SignerError::InvalidSignatures`] is returned.
+pub fn verify_spend_bundle(
+    spend_bundle: &SpendBundle,
+    constants: &AggSigConstants,
+) -> Result<(), SignerError> {
+    let mut allocator = Allocator::new();
+    let mut required_bls = Vec::new();
+    let mut invalid = Vec::new();
+
+    for coin_spend in &spend_bundle.coin_spends {
+        let coin_id = coin_spend.coin.coin_id();
+
+        for required in RequiredSignature::from_coin_spend(&mut allocator, coin_spend, constants)? {
+            match required {
+                RequiredSignature::Bls(required) => required_bls.push((coin_id, required)),
+                RequiredSignature::Secp(required) => {
+                    if !verify_secp_signature(&allocator, &required) {
+                        invalid.push(InvalidSignature::Secp(coin_id, required));
+                    }
+                }
+            }
+        }
+    }
+
+    let is_valid = if required_bls.is_empty() {
+        spend_bundle.aggregated_signature == Signature::default()
+    } else {
+        aggregate_verify(
+            &spend_bundle.aggregated_signature,
+            required_bls
+                .iter()
+                .map(|(_, required)| (&required.public_key, required.message())),
+        )
+    };
+
+    if !is_valid {
+        invalid.insert(0, InvalidSignature::Bls(required_bls));
+    }
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(SignerError::InvalidSignatures(invalid))
+    }
+}
+
+fn verify_secp_signature(allocator: &Allocator, required: &RequiredSecpSignature) -> bool {
+    match required.public_key {
+        SecpPublicKey::K1(public_key) => {
+            K1Signature::from_clvm(allocator, required.placeholder_ptr).is_ok_and(|signature| {
+                public_key.verify_prehashed(&required.message_hash, &signature)
+            })
+        }
+        SecpPublicKey::R1(public_key) => {
+            R1Signature::from_clvm(allocator, required.placeholder_ptr).is_ok_and(|signature| {
+                public_key.verify_prehashed(&required.message_hash, &signature)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::{sign, SecretKey};
+    use chia_protocol::{Bytes, Coin, CoinSpend, Program};
+    use chia_sdk_types::{AggSig, AggSigKind, AggSigMe, MAINNET_CONSTANTS};
+    use chia_secp::K1SecretKey;
+    use clvm_traits::{clvm_list, ToClvm};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn coin_spend(
+        allocator: &mut Allocator,
+        puzzle: impl ToClvm<Allocator>,
+        solution: impl ToClvm<Allocator>,
+    ) -> anyhow::Result<CoinSpend> {
+        let puzzle = puzzle.to_clvm(allocator)?;
+        let solution = solution.to_clvm(allocator)?;
+
+        Ok(CoinSpend::new(
+            Coin::new(Bytes32::new([1; 32]), Bytes32::new([2; 32]), 3),
+            Program::from_clvm(allocator, puzzle)?,
+            Program::from_clvm(allocator, solution)?,
+        ))
+    }
+
+    #[test]
+    fn test_verify_bls() -> anyhow::Result<()> {
+        let mut a = Allocator::new();
+        let constants = AggSigConstants::from(&*MAINNET_CONSTANTS);
+
+        let sk = SecretKey::from_seed(&[1; 32]);
+        let other_sk = SecretKey::from_seed(&[2; 32]);
+        let message = Bytes::from(b"Hello, world!".to_vec());
+
+        let coin_spend = coin_spend(&mut a, 1, [AggSigMe::new(sk.public_key(), message.clone())])?;
+
+        let required = RequiredBlsSignature::from_condition(
+            &coin_spend.coin,
+            AggSig::new(AggSigKind::Me, sk.public_key(), message),
+            &constants,
+        );
+
+        let valid = SpendBundle::new(vec![coin_spend.clone()], sign(&sk, required.message()));
+        verify_spend_bundle(&valid, &constants)?;
+
+        let missing = SpendBundle::new(vec![coin_spend.clone()], Signature::default());
+        let wrong = SpendBundle::new(vec![coin_spend], sign(&other_sk, required.message()));
+
+        for spend_bundle in [missing, wrong] {
+            let Err(SignerError::InvalidSignatures(invalid)) =
+                verify_spend_bundle(&spend_bundle, &constants)
+            else {
+                panic!("expected invalid signatures");
+            };
+
+            assert_eq!(invalid.len(), 1);
+
+            let InvalidSignature::Bls(items) = &invalid[0] else {
+                panic!("expected invalid bls signature");
+            };
+
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].0, spend_bundle.coin_spends[0].coin.coin_id());
+            assert_eq!(items[0].1.public_key, sk.public_key());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_secp() -> anyhow::Result<()> {
+        let mut a = Allocator::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(1337);
+        let constants = AggSigConstants::from(&*MAINNET_CONSTANTS);
+
+        let sk = K1SecretKey::from_bytes(&rng.gen())?;
+        let other_sk = K1SecretKey::from_bytes(&rng.gen())?;
+        let message_hash: [u8; 32] = rng.gen();
+
+        // Calls `secp256k1_verify` with the signature from the solution.
+        let op = Bytes::from(vec![0x13, 0xd6, 0x1f, 0x00]);
+        let puzzle = clvm_list!(op, (1, sk.public_key()), (1, Bytes32::new(message_hash)), 1);
+
+        let valid = coin_spend(&mut a, &puzzle, sk.sign_prehashed(&message_hash)?)?;
+        verify_spend_bundle(
+            &SpendBundle::new(vec![valid], Signature::default()),
+            &constants,
+        )?;
+
+        let wrong = coin_spend(&mut a, &puzzle, other_sk.sign_prehashed(&message_hash)?)?;
+        let coin_id = wrong.coin.coin_id();
+
+        let Err(SignerError::InvalidSignatures(invalid)) = verify_spend_bundle(
+            &SpendBundle::new(vec![wrong], Signature::default()),
+            &constants,
+        ) else {
+            panic!("expected invalid signatures");
+        };
+
+        assert_eq!(invalid.len(), 1);
+
+        let InvalidSignature::Secp(invalid_coin_id, required) = &invalid[0] else {
+            panic!("expected invalid secp signature");
+        };
+
+        assert_eq!(*invalid_coin_id, coin_id);
+        assert_eq!(required.public_key, SecpPublicKey::K1(sk.public_key()));
+        assert_eq!(required.message_hash, message_hash);
+
+        Ok(())
+    }
+}
@@ -17,4 +17,13 @@ pub enum SimulatorError {
 
     #[error("Missing key")]
     MissingKey,
+
+    #[error("Mempool conflict")]
+    MempoolConflict,
+
+    #[error("Streamable error: {0}")]
+    Streamable(#[from] chia_traits::Error),
+
+    #[error("Unsupported snapshot version: {0}")]
+    UnsupportedSnapshotVersion(u8),
 }
@@ -2,6 +2,7 @@ mod announcements;
 mod error;
 mod events;
 mod keys;
+mod mempool;
 mod peer_simulator;
 mod simulator;
 mod transaction;
@@ -10,10 +11,17 @@ pub use announcements::*;
 pub use error::*;
 pub use events::*;
 pub use keys::*;
+pub use mempool::*;
 pub use peer_simulator::*;
 pub use simulator::*;
 pub use transaction::*;
 
+#[cfg(feature = "experimental-vaults")]
+mod passkey;
+
+#[cfg(feature = "experimental-vaults")]
+pub use passkey::*;
+
 use chia_protocol::{Bytes32, Program};
 use clvm_traits::{FromClvm, ToClvm};
 use clvm_utils::tree_hash;
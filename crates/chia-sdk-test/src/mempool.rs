@@ -0,0 +1,61 @@
+use chia_consensus::gen::owned_conditions::OwnedSpendBundleConditions;
+use chia_protocol::{Bytes32, Coin, CoinState, SpendBundle};
+use indexmap::{IndexMap, IndexSet};
+
+/// The minimum amount that the fee must be increased by to replace conflicting mempool items.
+pub const MEMPOOL_MIN_FEE_INCREASE: u64 = 10_000_000;
+
+/// The result of submitting a spend bundle to the mempool.
+/// The values match the status codes used by `TransactionAck`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MempoolInclusionStatus {
+    /// The spend bundle was added to the mempool, and can be included in the next block.
+    Success = 1,
+    /// The spend bundle was added to the mempool, but its absolute timelocks haven't passed yet.
+    Pending = 2,
+}
+
+/// The result of processing a spend bundle with [`Simulator::new_transaction`](crate::Simulator::new_transaction).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionResult {
+    /// The spend bundle was included in a new block, which updated these coin states.
+    Included(IndexMap<Bytes32, CoinState>),
+    /// Mempool mode is enabled, so the spend bundle was added to the mempool instead.
+    Queued(MempoolInclusionStatus),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MempoolItem {
+    pub(crate) spend_bundle: SpendBundle,
+    pub(crate) fee: u128,
+    pub(crate) cost: u64,
+    pub(crate) removals: IndexSet<Bytes32>,
+    pub(crate) additions: IndexSet<Bytes32>,
+    /// The conditions are kept so that the spend bundle isn't validated again when a block is farmed.
+    pub(crate) conds: OwnedSpendBundleConditions,
+}
+
+impl MempoolItem {
+    pub(crate) fn new(spend_bundle: SpendBundle, conds: OwnedSpendBundleConditions) -> Self {
+        let mut removals = IndexSet::new();
+        let mut additions = IndexSet::new();
+
+        for spend in &conds.spends {
+            removals.insert(spend.coin_id);
+
+            for new_coin in &spend.create_coin {
+                additions.insert(Coin::new(spend.coin_id, new_coin.0, new_coin.1).coin_id());
+            }
+        }
+
+        Self {
+            spend_bundle,
+            fee: conds.removal_amount.saturating_sub(conds.addition_amount),
+            cost: conds.cost,
+            removals,
+            additions,
+            conds,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use chia_protocol::{Bytes, Bytes32};
+use chia_sdk_signer::{
+    encode_passkey_challenge, passkey_message_hash, PasskeyAssertion, WEBAUTHN_GET_TYPE,
+};
+use chia_secp::{R1PublicKey, R1SecretKey};
+use clvmr::sha2::Sha256;
+
+/// The user present and user verified flags of the authenticator data.
+const USER_PRESENT_VERIFIED: u8 = 0b0000_0101;
+
+/// A software `WebAuthn` authenticator, which signs challenges the same way a hardware
+/// passkey would. This allows passkey members to be tested without a browser or device.
+#[derive(Debug, Clone)]
+pub struct SoftwarePasskey {
+    secret_key: R1SecretKey,
+    rp_id: String,
+    origin: String,
+    sign_count: u32,
+}
+
+impl SoftwarePasskey {
+    pub fn new(secret_key: R1SecretKey) -> Self {
+        Self::with_origin(secret_key, "localhost", "http://localhost")
+    }
+
+    pub fn with_origin(secret_key: R1SecretKey, rp_id: &str, origin: &str) -> Self {
+        Self {
+            secret_key,
+            rp_id: rp_id.to_string(),
+            origin: origin.to_string(),
+            sign_count: 0,
+        }
+    }
+
+    pub fn public_key(&self) -> R1PublicKey {
+        self.secret_key.public_key()
+    }
+
+    /// Signs the challenge, incrementing the signature counter like a real authenticator.
+    pub fn sign(&mut self, challenge: Bytes32) -> Result<PasskeyAssertion, signature::Error> {
+        self.sign_count += 1;
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.rp_id.as_bytes());
+
+        let mut authenticator_data = hasher.finalize().to_vec();
+        authenticator_data.push(USER_PRESENT_VERIFIED);
+        authenticator_data.extend_from_slice(&self.sign_count.to_be_bytes());
+
+        let challenge = encode_passkey_challenge(challenge);
+        let origin = &self.origin;
+        let client_data_json = format!(
+            r#"{{"type":"{WEBAUTHN_GET_TYPE}","challenge":"{challenge}","origin":"{origin}"}}"#
+        )
+        .into_bytes();
+
+        let message_hash = passkey_message_hash(&authenticator_data, &client_data_json);
+        let signature = self.secret_key.sign_prehashed(&message_hash)?;
+
+        Ok(PasskeyAssertion::new(
+            Bytes::new(authenticator_data),
+            Bytes::new(client_data_json),
+            signature,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_signer::passkey_challenge;
+
+    use crate::test_r1_key;
+
+    use super::*;
+
+    #[test]
+    fn test_software_passkey() -> anyhow::Result<()> {
+        let mut passkey = SoftwarePasskey::new(test_r1_key()?);
+
+        let challenge = passkey_challenge(
+            Bytes32::new([1; 32]),
+            Bytes32::new([2; 32]),
+            Bytes32::new([3; 32]),
+        );
+
+        let first = passkey.sign(challenge)?;
+        first.verify(&passkey.public_key(), challenge)?;
+
+        let second = passkey.sign(challenge)?;
+        second.verify(&passkey.public_key(), challenge)?;
+        assert_ne!(first.authenticator_data, second.authenticator_data);
+
+        Ok(())
+    }
+}
@@ -2,9 +2,8 @@ use std::{net::SocketAddr, sync::Arc};
 
 use chia_protocol::{Bytes32, Coin, CoinState, Message};
 use chia_sdk_client::{Peer, PeerOptions};
-use error::PeerSimulatorError;
+use indexmap::IndexMap;
 use peer_map::PeerMap;
-use simulator_config::SimulatorConfig;
 use subscriptions::Subscriptions;
 use tokio::{
     net::TcpListener,
@@ -12,7 +11,7 @@ use tokio::{
     task::JoinHandle,
 };
 use tokio_tungstenite::connect_async;
-use ws_connection::ws_connection;
+use ws_connection::{broadcast_block, broadcast_reorg, ws_connection};
 
 use crate::Simulator;
 
@@ -22,10 +21,14 @@ mod simulator_config;
 mod subscriptions;
 mod ws_connection;
 
+pub use error::*;
+pub use simulator_config::*;
+
 #[derive(Debug)]
 pub struct PeerSimulator {
     config: Arc<SimulatorConfig>,
     addr: SocketAddr,
+    peer_map: PeerMap,
     simulator: Arc<Mutex<Simulator>>,
     subscriptions: Arc<Mutex<Subscriptions>>,
     join_handle: JoinHandle<()>,
@@ -43,15 +46,17 @@ impl PeerSimulator {
         let peer_map = PeerMap::default();
         let listener = TcpListener::bind(addr).await?;
         let addr = listener.local_addr()?;
-        let simulator = Arc::new(Mutex::new(Simulator::default()));
+        let simulator = Arc::new(Mutex::new(config.initial_simulator()));
         let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
         let config = Arc::new(config);
 
+        let peer_map_clone = peer_map.clone();
         let simulator_clone = simulator.clone();
         let subscriptions_clone = subscriptions.clone();
         let config_clone = config.clone();
 
         let join_handle = tokio::spawn(async move {
+            let peer_map = peer_map_clone;
             let simulator = simulator_clone;
             let subscriptions = subscriptions_clone;
             let config = config_clone;
@@ -78,6 +83,7 @@ impl PeerSimulator {
         Ok(Self {
             config,
             addr,
+            peer_map,
             simulator,
             subscriptions,
             join_handle,
@@ -123,7 +129,7 @@ impl PeerSimulator {
     }
 
     pub async fn reset(&self) -> Result<(), PeerSimulatorError> {
-        *self.simulator.lock().await = Simulator::default();
+        *self.simulator.lock().await = self.config.initial_simulator();
         *self.subscriptions.lock().await = Subscriptions::default();
         Ok(())
     }
@@ -151,6 +157,78 @@ impl PeerSimulator {
     pub async fn peak_hash(&self) -> Bytes32 {
         self.simulator.lock().await.header_hash()
     }
+
+    pub async fn timestamp(&self) -> u64 {
+        self.simulator.lock().await.timestamp()
+    }
+
+    /// Advances the current time without creating any blocks.
+    pub async fn pass_time(&self, seconds: u64) {
+        self.simulator.lock().await.pass_time(seconds);
+    }
+
+    /// Creates empty blocks and notifies connected peers of the new peak.
+    pub async fn pass_blocks(&self, blocks: u32) -> Result<(), PeerSimulatorError> {
+        let mut simulator = self.simulator.lock().await;
+        let subscriptions = self.subscriptions.lock().await;
+        simulator.pass_blocks(blocks);
+        let height = simulator.height();
+        broadcast_block(
+            &self.peer_map,
+            &simulator,
+            &subscriptions,
+            &IndexMap::new(),
+            height,
+        )
+        .await
+    }
+
+    /// Creates a block from the mempool, and sends the resulting coin state updates to peers.
+    pub async fn farm_block(&self) -> Result<(), PeerSimulatorError> {
+        let mut simulator = self.simulator.lock().await;
+        let subscriptions = self.subscriptions.lock().await;
+        let updates = simulator.farm_block()?;
+        let height = simulator.height();
+        broadcast_block(&self.peer_map, &simulator, &subscriptions, &updates, height).await
+    }
+
+    /// Rolls the chain back to the given height, and notifies peers of the reorg.
+    pub async fn rollback_to(&self, height: u32) -> Result<(), PeerSimulatorError> {
+        let mut simulator = self.simulator.lock().await;
+        let subscriptions = self.subscriptions.lock().await;
+        let (updates, pruned_hints) = simulator.rollback_with_hints(height);
+        broadcast_reorg(
+            &self.peer_map,
+            &simulator,
+            &subscriptions,
+            &updates,
+            &pruned_hints,
+            height,
+        )
+        .await
+    }
+
+    /// Creates a fork of the chain at the given height. See [`Simulator::fork`].
+    pub async fn fork(&self, height: u32) -> Simulator {
+        self.simulator.lock().await.fork(height)
+    }
+
+    /// Replaces the chain with a fork of it, and notifies peers of the reorg.
+    pub async fn switch_peak(&self, fork: Simulator) -> Result<(), PeerSimulatorError> {
+        let mut simulator = self.simulator.lock().await;
+        let subscriptions = self.subscriptions.lock().await;
+        let (fork_height, updates, pruned_hints) = simulator.switch_peak_with_hints(fork);
+        simulator.set_mempool_mode(self.config.mempool);
+        broadcast_reorg(
+            &self.peer_map,
+            &simulator,
+            &subscriptions,
+            &updates,
+            &pruned_hints,
+            fork_height,
+        )
+        .await
+    }
 }
 
 impl Drop for PeerSimulator {
@@ -163,13 +241,17 @@ impl Drop for PeerSimulator {
 mod tests {
     use chia_bls::{DerivableKey, PublicKey, Signature};
     use chia_protocol::{
-        Bytes, CoinSpend, CoinStateFilters, CoinStateUpdate, RespondCoinState, RespondPuzzleState,
-        SpendBundle,
+        Bytes, CoinSpend, CoinStateFilters, CoinStateUpdate, NewPeakWallet, ProtocolMessageTypes,
+        RespondCoinState, RespondPuzzleState, SpendBundle,
     };
     use chia_sdk_types::{AggSigMe, CreateCoin, Memos, Remark};
+    use chia_traits::Streamable;
     use clvmr::NodePtr;
 
-    use crate::{coin_state_updates, test_secret_key, test_transaction, to_program, to_puzzle};
+    use crate::{
+        coin_state_updates, test_secret_key, test_transaction, to_program, to_puzzle,
+        MempoolInclusionStatus,
+    };
 
     use super::*;
 
@@ -190,6 +272,246 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_pass_blocks() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let (_peer, mut receiver) = sim.connect_split().await?;
+
+        sim.pass_time(100).await;
+        sim.pass_blocks(3).await?;
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected NewPeakWallet message");
+        assert_eq!(message.msg_type, ProtocolMessageTypes::NewPeakWallet);
+
+        let new_peak = NewPeakWallet::from_bytes(&message.data)?;
+        assert_eq!(new_peak.height, 3);
+        assert_eq!(new_peak.header_hash, sim.peak_hash().await);
+        assert_eq!(sim.timestamp().await, 100);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mempool_mode() -> anyhow::Result<()> {
+        let sim = PeerSimulator::with_config(SimulatorConfig {
+            mempool: true,
+            ..Default::default()
+        })
+        .await?;
+        let (peer, mut receiver) = sim.connect_split().await?;
+
+        let (puzzle_hash, puzzle_reveal) = to_puzzle(1)?;
+        let coin = sim.mint_coin(puzzle_hash, 0).await;
+
+        peer.register_for_coin_updates(vec![coin.coin_id()], 0)
+            .await?;
+
+        let spend_bundle = SpendBundle::new(
+            vec![CoinSpend::new(coin, puzzle_reveal, to_program(())?)],
+            Signature::default(),
+        );
+
+        let ack = peer.send_transaction(spend_bundle).await?;
+        assert_eq!(ack.status, MempoolInclusionStatus::Success as u8);
+
+        // Nothing happens until a block is farmed.
+        assert_eq!(sim.height().await, 0);
+        assert!(receiver.try_recv().is_err());
+
+        sim.farm_block().await?;
+        assert_eq!(sim.height().await, 1);
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected NewPeakWallet message");
+        assert_eq!(message.msg_type, ProtocolMessageTypes::NewPeakWallet);
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected CoinStateUpdate message");
+        let update = CoinStateUpdate::from_bytes(&message.data)?;
+        assert_eq!(update.items.len(), 1);
+        assert_eq!(update.items[0].spent_height, Some(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollback() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let (peer, mut receiver) = sim.connect_split().await?;
+
+        let (puzzle_hash, puzzle_reveal) = to_puzzle(1)?;
+        let coin = sim.mint_coin(puzzle_hash, 0).await;
+        sim.pass_blocks(1).await?;
+
+        peer.register_for_coin_updates(vec![coin.coin_id()], 0)
+            .await?;
+
+        let spend_bundle = SpendBundle::new(
+            vec![CoinSpend::new(coin, puzzle_reveal, to_program(())?)],
+            Signature::default(),
+        );
+
+        let ack = peer.send_transaction(spend_bundle).await?;
+        assert_eq!(ack.status, 1);
+        assert_eq!(
+            sim.coin_state(coin.coin_id()).await.unwrap().spent_height,
+            Some(2)
+        );
+
+        // Skip the messages for the passed block and the transaction.
+        while receiver.try_recv().is_ok() {}
+
+        // Undo the block which spent the coin, and keep the block before it.
+        sim.rollback_to(1).await?;
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected NewPeakWallet message");
+        let new_peak = NewPeakWallet::from_bytes(&message.data)?;
+        assert_eq!(new_peak.height, 1);
+        assert_eq!(new_peak.fork_point_with_previous_peak, 1);
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected CoinStateUpdate message");
+        let update = CoinStateUpdate::from_bytes(&message.data)?;
+        assert_eq!(
+            update,
+            CoinStateUpdate::new(
+                1,
+                1,
+                sim.peak_hash().await,
+                vec![CoinState::new(coin, None, Some(0))]
+            )
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollback_hinted_coin() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let (peer, mut receiver) = sim.connect_split().await?;
+
+        let hint = Bytes32::new([42; 32]);
+        let (puzzle_hash, puzzle_reveal) = to_puzzle(1)?;
+
+        let coin = sim.mint_coin(puzzle_hash, 0).await;
+
+        peer.register_for_ph_updates(vec![hint], 0).await?;
+
+        let spend_bundle = SpendBundle::new(
+            vec![CoinSpend::new(
+                coin,
+                puzzle_reveal,
+                to_program([CreateCoin::new(puzzle_hash, 0, Some(Memos::new([hint])))])?,
+            )],
+            Signature::default(),
+        );
+
+        let ack = peer.send_transaction(spend_bundle).await?;
+        assert_eq!(ack.status, 1);
+
+        // Skip the messages for the transaction.
+        while receiver.try_recv().is_ok() {}
+
+        // The hinted coin no longer exists, so peers subscribed to the hint are notified.
+        sim.rollback_to(0).await?;
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected NewPeakWallet message");
+        assert_eq!(message.msg_type, ProtocolMessageTypes::NewPeakWallet);
+
+        let message = receiver
+            .recv()
+            .await
+            .expect("expected CoinStateUpdate message");
+        let update = CoinStateUpdate::from_bytes(&message.data)?;
+        assert_eq!(
+            update,
+            CoinStateUpdate::new(
+                0,
+                0,
+                sim.peak_hash().await,
+                vec![CoinState::new(
+                    Coin::new(coin.coin_id(), puzzle_hash, 0),
+                    None,
+                    None
+                )]
+            )
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_mempool_mode() -> anyhow::Result<()> {
+        let mut snapshot = Simulator::new();
+        snapshot.set_mempool_mode(true);
+
+        // The config decides whether transactions are queued, rather than the snapshot.
+        let sim = PeerSimulator::with_config(SimulatorConfig {
+            snapshot: Some(snapshot),
+            ..Default::default()
+        })
+        .await?;
+        let peer = sim.connect().await?;
+
+        let (puzzle_hash, puzzle_reveal) = to_puzzle(1)?;
+        let coin = sim.mint_coin(puzzle_hash, 0).await;
+
+        let spend_bundle = SpendBundle::new(
+            vec![CoinSpend::new(coin, puzzle_reveal, to_program(())?)],
+            Signature::default(),
+        );
+
+        let ack = peer.send_transaction(spend_bundle).await?;
+        assert_eq!(ack.status, MempoolInclusionStatus::Success as u8);
+        assert_eq!(sim.height().await, 1);
+        assert!(sim
+            .coin_state(coin.coin_id())
+            .await
+            .is_some_and(|coin_state| coin_state.spent_height == Some(1)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() -> anyhow::Result<()> {
+        let mut snapshot = Simulator::new();
+        let coin = snapshot.new_coin(Bytes32::default(), 1000);
+        snapshot.pass_blocks(10);
+
+        let sim = PeerSimulator::with_config(SimulatorConfig {
+            snapshot: Some(snapshot),
+            ..Default::default()
+        })
+        .await?;
+
+        assert_eq!(sim.height().await, 10);
+        assert!(sim.coin_state(coin.coin_id()).await.is_some());
+
+        sim.mint_coin(Bytes32::default(), 1).await;
+        sim.reset().await?;
+        assert_eq!(
+            sim.config().snapshot.as_ref(),
+            Some(&*sim.simulator.lock().await)
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_empty_transaction() -> anyhow::Result<()> {
         let sim = PeerSimulator::new().await?;
@@ -479,8 +801,8 @@ mod tests {
             .find(|cs| cs.coin.amount == 2)
             .copied();
 
-        let expected_1 = CoinState::new(Coin::new(coin.coin_id(), puzzle_hash, 1), None, Some(0));
-        let expected_2 = CoinState::new(Coin::new(coin.coin_id(), puzzle_hash, 2), None, Some(0));
+        let expected_1 = CoinState::new(Coin::new(coin.coin_id(), puzzle_hash, 1), None, Some(1));
+        let expected_2 = CoinState::new(Coin::new(coin.coin_id(), puzzle_hash, 2), None, Some(1));
 
         assert_eq!(found_1, Some(expected_1));
         assert_eq!(found_2, Some(expected_2));
@@ -511,13 +833,13 @@ mod tests {
         assert_eq!(ack.status, 1);
 
         let response = peer
-            .request_puzzle_and_solution(coin.coin_id(), 0)
+            .request_puzzle_and_solution(coin.coin_id(), 1)
             .await?
             .unwrap();
         assert_eq!(response.coin_name, coin.coin_id());
         assert_eq!(response.puzzle, puzzle_reveal);
         assert_eq!(response.solution, solution);
-        assert_eq!(response.height, 0);
+        assert_eq!(response.height, 1);
 
         Ok(())
     }
@@ -550,7 +872,7 @@ mod tests {
         let ack = peer.send_transaction(spend_bundle).await?;
         assert_eq!(ack.status, 1);
 
-        coin_state.spent_height = Some(0);
+        coin_state.spent_height = Some(1);
 
         let updates = coin_state_updates(&mut receiver);
         assert_eq!(updates.len(), 1);
@@ -594,7 +916,7 @@ mod tests {
         let updates = coin_state_updates(&mut receiver);
         assert_eq!(updates.len(), 1);
 
-        let coin_state = CoinState::new(child_coin, None, Some(0));
+        let coin_state = CoinState::new(child_coin, None, Some(1));
 
         assert_eq!(
             updates[0],
@@ -632,7 +954,7 @@ mod tests {
         let ack = peer.send_transaction(spend_bundle).await?;
         assert_eq!(ack.status, 1);
 
-        coin_state.spent_height = Some(0);
+        coin_state.spent_height = Some(1);
 
         let updates = coin_state_updates(&mut receiver);
         assert_eq!(updates.len(), 1);
@@ -676,7 +998,7 @@ mod tests {
         let updates = coin_state_updates(&mut receiver);
         assert_eq!(updates.len(), 1);
 
-        let coin_state = CoinState::new(child_coin, None, Some(0));
+        let coin_state = CoinState::new(child_coin, None, Some(1));
 
         assert_eq!(
             updates[0],
@@ -717,7 +1039,7 @@ mod tests {
         let ack = peer.send_transaction(spend_bundle).await?;
         assert_eq!(ack.status, 1);
 
-        coin_state.spent_height = Some(0);
+        coin_state.spent_height = Some(1);
 
         let updates = coin_state_updates(&mut receiver);
         assert_eq!(updates.len(), 1);
@@ -770,7 +1092,7 @@ mod tests {
                 vec![CoinState::new(
                     Coin::new(coin.coin_id(), puzzle_hash, 0),
                     None,
-                    Some(0)
+                    Some(1)
                 )]
             )
         );
@@ -813,7 +1135,7 @@ mod tests {
         let ack = peer.send_transaction(spend_bundle).await?;
         assert_eq!(ack.status, 1);
 
-        coin_state.spent_height = Some(0);
+        coin_state.spent_height = Some(1);
 
         let response = peer
             .request_coin_state(
@@ -874,7 +1196,7 @@ mod tests {
         let ack = peer.send_transaction(spend_bundle).await?;
         assert_eq!(ack.status, 1);
 
-        coin_state.spent_height = Some(0);
+        coin_state.spent_height = Some(1);
 
         let response = peer
             .request_puzzle_state(
@@ -1,12 +1,21 @@
 use chia_consensus::consensus_constants::ConsensusConstants;
 use chia_sdk_types::TESTNET11_CONSTANTS;
 
+use crate::Simulator;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SimulatorConfig {
     pub constants: ConsensusConstants,
     pub max_subscriptions: usize,
     pub max_response_coins: usize,
     pub puzzle_state_batch_size: usize,
+    /// Whether transactions are added to the mempool instead of immediately creating a block.
+    /// In this mode, blocks are created with [`PeerSimulator::farm_block`](crate::PeerSimulator::farm_block).
+    /// This overrides the mempool mode of the snapshot, if there is one.
+    pub mempool: bool,
+    /// The initial state of the simulator, which is also used when it's reset.
+    /// This can be loaded from a file with [`Simulator::load`].
+    pub snapshot: Option<Simulator>,
 }
 
 impl Default for SimulatorConfig {
@@ -16,6 +25,17 @@ impl Default for SimulatorConfig {
             max_subscriptions: 200_000,
             max_response_coins: 100_000,
             puzzle_state_batch_size: 30_000,
+            mempool: false,
+            snapshot: None,
         }
     }
 }
+
+impl SimulatorConfig {
+    /// Creates the simulator from the snapshot, with mempool mode set from this config.
+    pub(crate) fn initial_simulator(&self) -> Simulator {
+        let mut simulator = self.snapshot.clone().unwrap_or_default();
+        simulator.set_mempool_mode(self.mempool);
+        simulator
+    }
+}
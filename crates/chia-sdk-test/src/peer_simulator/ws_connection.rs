@@ -8,7 +8,7 @@ use chia_protocol::{
     RequestPuzzleSolution, RequestPuzzleState, RequestRemoveCoinSubscriptions,
     RequestRemovePuzzleSubscriptions, RespondChildren, RespondCoinState, RespondPuzzleSolution,
     RespondPuzzleState, RespondRemoveCoinSubscriptions, RespondRemovePuzzleSubscriptions,
-    RespondToCoinUpdates, RespondToPhUpdates, SendTransaction, SpendBundle, TransactionAck,
+    RespondToCoinUpdates, RespondToPhUpdates, SendTransaction, TransactionAck,
 };
 use chia_traits::Streamable;
 use clvmr::NodePtr;
@@ -25,7 +25,7 @@ use tokio_tungstenite::{
     WebSocketStream,
 };
 
-use crate::{Simulator, SimulatorError};
+use crate::{PrunedHints, Simulator, SimulatorError, TransactionResult};
 
 use super::{
     error::PeerSimulatorError, peer_map::Ws, simulator_config::SimulatorConfig,
@@ -92,28 +92,26 @@ async fn handle_initial_peak(
     tx: &mut UnboundedSender<tungstenite::Message>,
     sim: &Mutex<Simulator>,
 ) -> Result<(), PeerSimulatorError> {
-    let (header_hash, height) = {
+    let new_peak = {
         let sim = sim.lock().await;
-        (sim.header_hash(), sim.height())
+        new_peak_wallet(&sim, sim.height())?
     };
-
-    tx.send(
-        Message {
-            msg_type: ProtocolMessageTypes::NewPeakWallet,
-            id: None,
-            data: NewPeakWallet::new(header_hash, height, 0, height)
-                .to_bytes()
-                .unwrap()
-                .into(),
-        }
-        .to_bytes()?
-        .into(),
-    )
-    .await?;
-
+    tx.send(new_peak.into()).await?;
     Ok(())
 }
 
+fn new_peak_wallet(simulator: &Simulator, fork_height: u32) -> Result<Vec<u8>, PeerSimulatorError> {
+    Ok(Message {
+        msg_type: ProtocolMessageTypes::NewPeakWallet,
+        id: None,
+        data: NewPeakWallet::new(simulator.header_hash(), simulator.height(), 0, fork_height)
+            .to_bytes()
+            .unwrap()
+            .into(),
+    }
+    .to_bytes()?)
+}
+
 async fn handle_message(
     peer_map: PeerMap,
     config: &SimulatorConfig,
@@ -202,12 +200,12 @@ async fn handle_message(
     Ok(())
 }
 
-fn new_transaction(
-    simulator: &mut MutexGuard<'_, Simulator>,
-    subscriptions: &mut MutexGuard<'_, Subscriptions>,
-    spend_bundle: SpendBundle,
-) -> Result<IndexMap<SocketAddr, IndexSet<CoinState>>, PeerSimulatorError> {
-    let updates = simulator.new_transaction(spend_bundle)?;
+fn peer_updates(
+    simulator: &Simulator,
+    subscriptions: &Subscriptions,
+    updates: &IndexMap<Bytes32, CoinState>,
+    pruned_hints: &PrunedHints,
+) -> IndexMap<SocketAddr, IndexSet<CoinState>> {
     let peers = subscriptions.peers();
 
     let mut peer_updates = IndexMap::new();
@@ -226,25 +224,20 @@ fn new_transaction(
             .cloned()
             .unwrap_or_default();
 
-        for &coin_id in updates.keys() {
-            let Some(coin_state) = simulator.coin_state(coin_id) else {
-                continue;
-            };
-
-            if coin_subscriptions.contains(&coin_id)
+        for (coin_id, coin_state) in updates {
+            if coin_subscriptions.contains(coin_id)
                 || puzzle_subscriptions.contains(&coin_state.coin.puzzle_hash)
             {
-                coin_states.insert(coin_state);
+                coin_states.insert(*coin_state);
             }
         }
 
         for &hint in &puzzle_subscriptions {
             let coin_ids = simulator.hinted_coins(hint);
+            let pruned_coin_ids = pruned_hints.get(&hint).into_iter().flatten().copied();
 
-            for coin_id in coin_ids {
-                if updates.contains_key(&coin_id) {
-                    coin_states.extend(simulator.coin_state(coin_id));
-                }
+            for coin_id in coin_ids.into_iter().chain(pruned_coin_ids) {
+                coin_states.extend(updates.get(&coin_id).copied());
             }
         }
 
@@ -255,50 +248,43 @@ fn new_transaction(
         peer_updates.insert(peer, coin_states);
     }
 
-    Ok(peer_updates)
+    peer_updates
 }
 
-async fn send_transaction(
-    peer_map: PeerMap,
-    request: SendTransaction,
-    mut simulator: MutexGuard<'_, Simulator>,
-    mut subscriptions: MutexGuard<'_, Subscriptions>,
-) -> Result<Bytes, PeerSimulatorError> {
-    let transaction_id = request.transaction.name();
-
-    let updates = match new_transaction(&mut simulator, &mut subscriptions, request.transaction) {
-        Ok(updates) => updates,
-        Err(error) => {
-            tracing::error!("error processing transaction: {:?}", &error);
-
-            let error_code = match error {
-                PeerSimulatorError::Simulator(SimulatorError::Validation(error_code)) => error_code,
-                _ => ErrorCode::Unknown,
-            };
-
-            return Ok(TransactionAck::new(
-                transaction_id,
-                3,
-                Some(format!("{:?}", ValidationErr(NodePtr::NIL, error_code))),
-            )
-            .to_bytes()?
-            .into());
-        }
-    };
+/// Sends the new peak to every peer, and the updated coin states to subscribed peers.
+pub(crate) async fn broadcast_block(
+    peer_map: &PeerMap,
+    simulator: &Simulator,
+    subscriptions: &Subscriptions,
+    updates: &IndexMap<Bytes32, CoinState>,
+    height: u32,
+) -> Result<(), PeerSimulatorError> {
+    broadcast_reorg(
+        peer_map,
+        simulator,
+        subscriptions,
+        updates,
+        &PrunedHints::new(),
+        height,
+    )
+    .await
+}
 
+/// Sends the new peak after a reorg to every peer, and the updated coin states to subscribed peers.
+/// The hints of coins that were removed by the reorg are no longer in the simulator, so they're
+/// passed in to notify peers subscribed to them.
+pub(crate) async fn broadcast_reorg(
+    peer_map: &PeerMap,
+    simulator: &Simulator,
+    subscriptions: &Subscriptions,
+    updates: &IndexMap<Bytes32, CoinState>,
+    pruned_hints: &PrunedHints,
+    fork_height: u32,
+) -> Result<(), PeerSimulatorError> {
+    let updates = peer_updates(simulator, subscriptions, updates, pruned_hints);
     let header_hash = simulator.header_hash();
+    let new_peak = new_peak_wallet(simulator, fork_height)?;
 
-    let new_peak = Message {
-        msg_type: ProtocolMessageTypes::NewPeakWallet,
-        id: None,
-        data: NewPeakWallet::new(header_hash, simulator.height(), 0, simulator.height())
-            .to_bytes()
-            .unwrap()
-            .into(),
-    }
-    .to_bytes()?;
-
-    // Send updates to peers.
     for (addr, mut peer) in peer_map.peers().await {
         peer.send(new_peak.clone().into()).await?;
 
@@ -311,7 +297,7 @@ async fn send_transaction(
             id: None,
             data: CoinStateUpdate::new(
                 simulator.height(),
-                simulator.height(),
+                fork_height,
                 header_hash,
                 peer_updates.into_iter().collect(),
             )
@@ -324,11 +310,56 @@ async fn send_transaction(
         peer.send(update.into()).await?;
     }
 
+    Ok(())
+}
+
+async fn send_transaction(
+    peer_map: PeerMap,
+    request: SendTransaction,
+    mut simulator: MutexGuard<'_, Simulator>,
+    subscriptions: MutexGuard<'_, Subscriptions>,
+) -> Result<Bytes, PeerSimulatorError> {
+    let transaction_id = request.transaction.name();
+
+    let updates = match simulator.new_transaction(request.transaction) {
+        Ok(TransactionResult::Included(updates)) => updates,
+        // Peers aren't notified about mempool items until a block is farmed.
+        Ok(TransactionResult::Queued(status)) => {
+            return Ok(TransactionAck::new(transaction_id, status as u8, None)
+                .to_bytes()?
+                .into());
+        }
+        Err(error) => return transaction_failed(transaction_id, &error),
+    };
+
+    let height = simulator.height();
+    broadcast_block(&peer_map, &simulator, &subscriptions, &updates, height).await?;
+
     Ok(TransactionAck::new(transaction_id, 1, None)
         .to_bytes()?
         .into())
 }
 
+fn transaction_failed(
+    transaction_id: Bytes32,
+    error: &SimulatorError,
+) -> Result<Bytes, PeerSimulatorError> {
+    tracing::error!("error processing transaction: {:?}", error);
+
+    let error_code = match error {
+        SimulatorError::Validation(error_code) => *error_code,
+        _ => ErrorCode::Unknown,
+    };
+
+    Ok(TransactionAck::new(
+        transaction_id,
+        3,
+        Some(format!("{:?}", ValidationErr(NodePtr::NIL, error_code))),
+    )
+    .to_bytes()?
+    .into())
+}
+
 fn register_for_coin_updates(
     peer: SocketAddr,
     request: RegisterForCoinUpdates,
@@ -2,7 +2,8 @@ use std::collections::HashSet;
 
 use chia_bls::{DerivableKey, PublicKey, SecretKey};
 use chia_consensus::{
-    gen::validation_error::ErrorCode, spendbundle_validation::validate_clvm_and_signature,
+    gen::{owned_conditions::OwnedSpendBundleConditions, validation_error::ErrorCode},
+    spendbundle_validation::validate_clvm_and_signature,
 };
 use chia_protocol::{Bytes32, Coin, CoinSpend, CoinState, Program, SpendBundle};
 use chia_puzzles::standard::StandardArgs;
@@ -10,15 +11,36 @@ use chia_sdk_types::TESTNET11_CONSTANTS;
 use fastrand::Rng;
 use indexmap::{IndexMap, IndexSet};
 
-use crate::{sign_transaction, test_secret_key, SimulatorError};
+use crate::{
+    sign_transaction, test_secret_key, MempoolInclusionStatus, MempoolItem, SimulatorError,
+    TransactionResult, MEMPOOL_MIN_FEE_INCREASE,
+};
+
+mod snapshot;
+
+/// The ids of the coins with each hint that were removed from the simulator by a reorg.
+pub(crate) type PrunedHints = IndexMap<Bytes32, IndexSet<Bytes32>>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Simulator {
     rng: Rng,
     height: u32,
     header_hashes: Vec<Bytes32>,
+    timestamp: u64,
+    block_timestamps: Vec<u64>,
     coin_states: IndexMap<Bytes32, CoinState>,
     hinted_coins: IndexMap<Bytes32, IndexSet<Bytes32>>,
+    coin_timestamps: IndexMap<Bytes32, u64>,
+    puzzle_and_solutions: IndexMap<Bytes32, (Program, Program)>,
+    mempool: IndexMap<Bytes32, MempoolItem>,
+    mempool_mode: bool,
+}
+
+#[derive(Debug, Default)]
+struct PendingBlock {
+    updates: IndexMap<Bytes32, CoinState>,
+    hints: IndexMap<Bytes32, IndexSet<Bytes32>>,
+    timestamps: IndexMap<Bytes32, u64>,
     puzzle_and_solutions: IndexMap<Bytes32, (Program, Program)>,
 }
 
@@ -42,9 +64,14 @@ impl Simulator {
             rng,
             height: 0,
             header_hashes: vec![header_hash.into()],
+            timestamp: 0,
+            block_timestamps: vec![0],
             coin_states: IndexMap::new(),
             hinted_coins: IndexMap::new(),
+            coin_timestamps: IndexMap::new(),
             puzzle_and_solutions: IndexMap::new(),
+            mempool: IndexMap::new(),
+            mempool_mode: false,
         }
     }
 
@@ -60,9 +87,39 @@ impl Simulator {
         self.header_hashes.get(height as usize).copied()
     }
 
+    /// The current time, which is used to validate time related conditions.
+    /// This only changes when [`Simulator::pass_time`] is called.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The timestamp of the block at the given height.
+    pub fn timestamp_of(&self, height: u32) -> Option<u64> {
+        self.block_timestamps.get(height as usize).copied()
+    }
+
+    /// The time at which a coin was created, used for relative time conditions.
+    pub fn created_timestamp(&self, coin_id: Bytes32) -> Option<u64> {
+        self.coin_timestamps.get(&coin_id).copied()
+    }
+
+    /// Advances the current time without creating any blocks.
+    pub fn pass_time(&mut self, seconds: u64) {
+        self.timestamp += seconds;
+    }
+
+    /// Creates empty blocks without advancing the current time.
+    pub fn pass_blocks(&mut self, blocks: u32) {
+        for _ in 0..blocks {
+            self.create_block();
+        }
+    }
+
+    /// Adds a coin to the current block, as if it had been created at the current height.
     pub fn insert_coin(&mut self, coin: Coin) {
         let coin_state = CoinState::new(coin, None, Some(self.height));
         self.coin_states.insert(coin.coin_id(), coin_state);
+        self.coin_timestamps.insert(coin.coin_id(), self.timestamp);
     }
 
     pub fn new_coin(&mut self, puzzle_hash: Bytes32, amount: u64) -> Coin {
@@ -137,28 +194,331 @@ impl Simulator {
         &mut self,
         coin_spends: Vec<CoinSpend>,
         secret_keys: &[SecretKey],
-    ) -> Result<IndexMap<Bytes32, CoinState>, SimulatorError> {
+    ) -> Result<TransactionResult, SimulatorError> {
         let signature = sign_transaction(&coin_spends, secret_keys)?;
         self.new_transaction(SpendBundle::new(coin_spends, signature))
     }
 
-    /// Processes a spend bunndle and returns the updated coin states.
+    /// Processes a spend bundle by immediately including it in a new block, bypassing the mempool.
+    ///
+    /// If mempool mode is enabled, the spend bundle is queued with [`Simulator::submit_transaction`]
+    /// instead, and no coin states are updated until [`Simulator::farm_block`] is called.
     pub fn new_transaction(
         &mut self,
         spend_bundle: SpendBundle,
-    ) -> Result<IndexMap<Bytes32, CoinState>, SimulatorError> {
+    ) -> Result<TransactionResult, SimulatorError> {
+        if self.mempool_mode {
+            return Ok(TransactionResult::Queued(
+                self.submit_transaction(spend_bundle)?,
+            ));
+        }
+
+        let block = self.prepare_block(&spend_bundle, TESTNET11_CONSTANTS.max_block_cost_clvm)?;
+        Ok(TransactionResult::Included(self.commit_block(block)))
+    }
+
+    /// Validates a spend bundle and adds it to the mempool, rather than immediately creating a block.
+    /// The spend bundle can depend on coins created by other spend bundles in the mempool.
+    ///
+    /// If it spends the same coins as existing mempool items, it must spend all of their coins,
+    /// pay a higher fee per cost, and increase the fee by at least [`MEMPOOL_MIN_FEE_INCREASE`].
+    /// In that case, the conflicting items will be replaced.
+    pub fn submit_transaction(
+        &mut self,
+        spend_bundle: SpendBundle,
+    ) -> Result<MempoolInclusionStatus, SimulatorError> {
+        let transaction_id = spend_bundle.name();
+
+        if self.mempool.contains_key(&transaction_id) {
+            return Ok(MempoolInclusionStatus::Success);
+        }
+
+        let conds =
+            self.validate_spend_bundle(&spend_bundle, TESTNET11_CONSTANTS.max_block_cost_clvm)?;
+
+        // The earliest block that the spend bundle could be included in.
+        let block_height = self.height + 1;
+
+        if let Some(height) = conds.before_height_absolute {
+            if height <= block_height {
+                return Err(SimulatorError::Validation(
+                    ErrorCode::AssertBeforeHeightAbsoluteFailed,
+                ));
+            }
+        }
+
+        if let Some(seconds) = conds.before_seconds_absolute {
+            if seconds <= self.timestamp {
+                return Err(SimulatorError::Validation(
+                    ErrorCode::AssertBeforeSecondsAbsoluteFailed,
+                ));
+            }
+        }
+
+        let status =
+            if block_height < conds.height_absolute || self.timestamp < conds.seconds_absolute {
+                MempoolInclusionStatus::Pending
+            } else {
+                MempoolInclusionStatus::Success
+            };
+
+        let item = MempoolItem::new(spend_bundle, conds);
+
+        for &coin_id in &item.removals {
+            self.check_spendable(coin_id, &item.additions)?;
+        }
+
+        let conflicts: Vec<Bytes32> = self
+            .mempool
+            .iter()
+            .filter(|(_, other)| !other.removals.is_disjoint(&item.removals))
+            .map(|(id, _)| *id)
+            .collect();
+
+        if !conflicts.is_empty() {
+            let mut conflicting_fee = 0;
+            let mut conflicting_cost = 0;
+
+            for id in &conflicts {
+                let other = &self.mempool[id];
+
+                if !other.removals.is_subset(&item.removals) {
+                    return Err(SimulatorError::MempoolConflict);
+                }
+
+                conflicting_fee += other.fee;
+                conflicting_cost += u128::from(other.cost);
+            }
+
+            if item.fee * conflicting_cost <= conflicting_fee * u128::from(item.cost)
+                || item.fee < conflicting_fee + u128::from(MEMPOOL_MIN_FEE_INCREASE)
+            {
+                return Err(SimulatorError::MempoolConflict);
+            }
+
+            for id in conflicts {
+                self.mempool.shift_remove(&id);
+            }
+
+            self.evict_mempool_items();
+        }
+
+        self.mempool.insert(transaction_id, item);
+
+        Ok(status)
+    }
+
+    /// Creates a new block from the items in the mempool, in order of fee per cost,
+    /// until the maximum block cost is reached. Items which aren't valid yet are left in the mempool.
+    pub fn farm_block(&mut self) -> Result<IndexMap<Bytes32, CoinState>, SimulatorError> {
+        let mut items: Vec<(Bytes32, MempoolItem)> = self
+            .mempool
+            .iter()
+            .map(|(id, item)| (*id, item.clone()))
+            .collect();
+
+        items.sort_by(|(_, a), (_, b)| {
+            (b.fee * u128::from(a.cost)).cmp(&(a.fee * u128::from(b.cost)))
+        });
+
+        let max_cost = TESTNET11_CONSTANTS.max_block_cost_clvm;
+        let mut included = IndexSet::new();
+        let mut cost = 0;
+        let mut block = PendingBlock::default();
+
+        // Items can depend on lower priority items, so keep going until nothing else can be included.
+        loop {
+            let mut progress = false;
+
+            for (id, item) in &items {
+                if included.contains(id) || cost + item.cost > max_cost {
+                    continue;
+                }
+
+                // The item was validated when it was submitted, so only the coin state is checked here.
+                if self
+                    .apply_spend_bundle(&mut block, &item.spend_bundle, &item.conds)
+                    .is_err()
+                {
+                    continue;
+                }
+
+                cost += item.cost;
+                included.insert(*id);
+                progress = true;
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        for id in &included {
+            self.mempool.shift_remove(id);
+        }
+
+        if included.is_empty() {
+            self.create_block();
+            return Ok(IndexMap::new());
+        }
+
+        Ok(self.commit_block(block))
+    }
+
+    /// Rolls the chain back to the given height, undoing coin creations and spends in later blocks.
+    /// Returns the updated coin states, where coins that no longer exist have no created height.
+    ///
+    /// The blocks up to and including the given height are kept, along with the coins created in them.
+    pub fn rollback_to(&mut self, height: u32) -> IndexMap<Bytes32, CoinState> {
+        self.rollback_with_hints(height).0
+    }
+
+    /// Rolls back like [`Simulator::rollback_to`], and also returns the hints of the coins that no
+    /// longer exist, since peers subscribed to those hints need to be notified.
+    pub(crate) fn rollback_with_hints(
+        &mut self,
+        height: u32,
+    ) -> (IndexMap<Bytes32, CoinState>, PrunedHints) {
+        let mut updates = IndexMap::new();
+
+        if height >= self.height {
+            return (updates, PrunedHints::new());
+        }
+
+        for (coin_id, coin_state) in &mut self.coin_states {
+            if coin_state
+                .created_height
+                .is_some_and(|created| created > height)
+            {
+                updates.insert(*coin_id, CoinState::new(coin_state.coin, None, None));
+            } else if coin_state.spent_height.is_some_and(|spent| spent > height) {
+                coin_state.spent_height = None;
+                self.puzzle_and_solutions.shift_remove(coin_id);
+                updates.insert(*coin_id, *coin_state);
+            }
+        }
+
+        self.coin_states.retain(|_, coin_state| {
+            coin_state
+                .created_height
+                .is_some_and(|created| created <= height)
+        });
+        self.coin_timestamps
+            .retain(|coin_id, _| self.coin_states.contains_key(coin_id));
+        self.puzzle_and_solutions
+            .retain(|coin_id, _| self.coin_states.contains_key(coin_id));
+        let pruned_hints = self.prune_hints();
+
+        self.height = height;
+        self.header_hashes.truncate(height as usize + 1);
+        self.block_timestamps.truncate(height as usize + 1);
+        self.timestamp = self.block_timestamps[height as usize];
+
+        self.evict_mempool_items();
+
+        (updates, pruned_hints)
+    }
+
+    /// Creates a copy of the simulator rolled back to the given height, which will produce different blocks.
+    /// Spend bundles can be applied to the fork, and then it can replace the chain with [`Simulator::switch_peak`].
+    #[must_use]
+    pub fn fork(&mut self, height: u32) -> Self {
+        let mut fork = self.clone();
+        fork.rng = Rng::with_seed(self.rng.u64(..));
+        fork.rollback_to(height);
+        fork
+    }
+
+    /// Replaces the chain with a fork of it, and returns the fork height along with the updated coin states.
+    /// The fork height is the last block that both chains have in common, and coins that only existed
+    /// in the previous chain are returned without a created height.
+    pub fn switch_peak(&mut self, fork: Self) -> (u32, IndexMap<Bytes32, CoinState>) {
+        let (fork_height, updates, _) = self.switch_peak_with_hints(fork);
+        (fork_height, updates)
+    }
+
+    /// Switches peak like [`Simulator::switch_peak`], and also returns the hints of the coins that
+    /// no longer exist, since peers subscribed to those hints need to be notified.
+    pub(crate) fn switch_peak_with_hints(
+        &mut self,
+        mut fork: Self,
+    ) -> (u32, IndexMap<Bytes32, CoinState>, PrunedHints) {
+        let common_blocks = self
+            .header_hashes
+            .iter()
+            .zip(&fork.header_hashes)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let fork_height = u32::try_from(common_blocks.saturating_sub(1)).unwrap();
+
+        let mut updates = IndexMap::new();
+
+        for (coin_id, coin_state) in &self.coin_states {
+            let new_state = fork
+                .coin_states
+                .get(coin_id)
+                .copied()
+                .unwrap_or(CoinState::new(coin_state.coin, None, None));
+
+            if new_state != *coin_state {
+                updates.insert(*coin_id, new_state);
+            }
+        }
+
+        for (coin_id, coin_state) in &fork.coin_states {
+            if !self.coin_states.contains_key(coin_id) {
+                updates.insert(*coin_id, *coin_state);
+            }
+        }
+
+        // Hints added after the fork was created are kept, as long as the coins exist in the fork.
+        for (hint, coin_ids) in &self.hinted_coins {
+            fork.hinted_coins
+                .entry(*hint)
+                .or_default()
+                .extend(coin_ids.iter().copied());
+        }
+        let pruned_hints = fork.prune_hints();
+
+        *self = fork;
+        self.evict_mempool_items();
+
+        (fork_height, updates, pruned_hints)
+    }
+
+    pub fn mempool_mode(&self) -> bool {
+        self.mempool_mode
+    }
+
+    /// Enables or disables mempool mode. When enabled, [`Simulator::new_transaction`] queues spend
+    /// bundles in the mempool, and blocks are only created by [`Simulator::farm_block`].
+    pub fn set_mempool_mode(&mut self, enabled: bool) {
+        self.mempool_mode = enabled;
+    }
+
+    pub fn mempool_ids(&self) -> Vec<Bytes32> {
+        self.mempool.keys().copied().collect()
+    }
+
+    pub fn mempool_item(&self, transaction_id: Bytes32) -> Option<SpendBundle> {
+        self.mempool
+            .get(&transaction_id)
+            .map(|item| item.spend_bundle.clone())
+    }
+
+    fn validate_spend_bundle(
+        &self,
+        spend_bundle: &SpendBundle,
+        max_cost: u64,
+    ) -> Result<OwnedSpendBundleConditions, SimulatorError> {
         if spend_bundle.coin_spends.is_empty() {
             return Err(SimulatorError::Validation(ErrorCode::InvalidSpendBundle));
         }
 
-        // TODO: Fix cost
-        let (conds, _pairings, _duration) = validate_clvm_and_signature(
-            &spend_bundle,
-            7_700_000_000,
-            &TESTNET11_CONSTANTS,
-            self.height,
-        )
-        .map_err(SimulatorError::Validation)?;
+        let (conds, _pairings, _duration) =
+            validate_clvm_and_signature(spend_bundle, max_cost, &TESTNET11_CONSTANTS, self.height)
+                .map_err(SimulatorError::Validation)?;
 
         let puzzle_hashes: HashSet<Bytes32> =
             conds.spends.iter().map(|spend| spend.puzzle_hash).collect();
@@ -173,45 +533,147 @@ impl Simulator {
             return Err(SimulatorError::Validation(ErrorCode::InvalidSpendBundle));
         }
 
+        Ok(conds)
+    }
+
+    /// Checks that a coin is unspent, or will be created by the mempool or the spend bundle itself.
+    fn check_spendable(
+        &self,
+        coin_id: Bytes32,
+        additions: &IndexSet<Bytes32>,
+    ) -> Result<(), SimulatorError> {
+        if additions.contains(&coin_id) {
+            return Ok(());
+        }
+
+        if let Some(coin_state) = self.coin_states.get(&coin_id) {
+            if coin_state.spent_height.is_some() {
+                return Err(SimulatorError::Validation(ErrorCode::DoubleSpend));
+            }
+            return Ok(());
+        }
+
+        if self
+            .mempool
+            .values()
+            .any(|item| item.additions.contains(&coin_id))
+        {
+            return Ok(());
+        }
+
+        Err(SimulatorError::Validation(ErrorCode::UnknownUnspent))
+    }
+
+    /// Removes the hints of coins that no longer exist, and returns them.
+    fn prune_hints(&mut self) -> PrunedHints {
+        let mut pruned_hints = PrunedHints::new();
+
+        for (hint, coin_ids) in &mut self.hinted_coins {
+            coin_ids.retain(|coin_id| {
+                let exists = self.coin_states.contains_key(coin_id);
+
+                if !exists {
+                    pruned_hints.entry(*hint).or_default().insert(*coin_id);
+                }
+
+                exists
+            });
+        }
+
+        self.hinted_coins.retain(|_, coin_ids| !coin_ids.is_empty());
+
+        pruned_hints
+    }
+
+    /// Removes mempool items which spend coins that have been spent or will no longer be created.
+    fn evict_mempool_items(&mut self) {
+        loop {
+            let invalid: Vec<Bytes32> = self
+                .mempool
+                .iter()
+                .filter(|(_, item)| {
+                    item.removals
+                        .iter()
+                        .any(|&coin_id| self.check_spendable(coin_id, &item.additions).is_err())
+                })
+                .map(|(id, _)| *id)
+                .collect();
+
+            if invalid.is_empty() {
+                break;
+            }
+
+            for id in invalid {
+                self.mempool.shift_remove(&id);
+            }
+        }
+    }
+
+    /// Validates a spend bundle against the current state, and calculates the block that would include it.
+    fn prepare_block(
+        &self,
+        spend_bundle: &SpendBundle,
+        max_cost: u64,
+    ) -> Result<PendingBlock, SimulatorError> {
+        let conds = self.validate_spend_bundle(spend_bundle, max_cost)?;
+        let mut block = PendingBlock::default();
+        self.apply_spend_bundle(&mut block, spend_bundle, &conds)?;
+        Ok(block)
+    }
+
+    /// Checks the conditions of an already validated spend bundle against the current state
+    /// and the spends before it in the pending block, and adds its coin updates to the block.
+    /// The block is left unchanged if the spend bundle can't be included.
+    fn apply_spend_bundle(
+        &self,
+        block: &mut PendingBlock,
+        spend_bundle: &SpendBundle,
+        conds: &OwnedSpendBundleConditions,
+    ) -> Result<(), SimulatorError> {
         let mut removed_coins = IndexMap::new();
         let mut added_coins = IndexMap::new();
+        let mut added_timestamps = IndexMap::new();
         let mut added_hints = IndexMap::new();
         let mut puzzle_solutions = IndexMap::new();
 
-        if self.height < conds.height_absolute {
+        // Coins are recorded at the height of the block that will contain them.
+        let block_height = self.height + 1;
+
+        if block_height < conds.height_absolute {
             return Err(SimulatorError::Validation(
                 ErrorCode::AssertHeightAbsoluteFailed,
             ));
         }
 
-        // TODO: Tick time differently?
-        if u64::from(self.height) < conds.seconds_absolute {
+        if self.timestamp < conds.seconds_absolute {
             return Err(SimulatorError::Validation(
                 ErrorCode::AssertSecondsAbsoluteFailed,
             ));
         }
 
         if let Some(height) = conds.before_height_absolute {
-            if height < self.height {
+            if height <= block_height {
                 return Err(SimulatorError::Validation(
                     ErrorCode::AssertBeforeHeightAbsoluteFailed,
                 ));
             }
         }
 
-        // TODO: Tick time differently?
         if let Some(seconds) = conds.before_seconds_absolute {
-            if seconds < self.height.into() {
+            if seconds <= self.timestamp {
                 return Err(SimulatorError::Validation(
                     ErrorCode::AssertBeforeSecondsAbsoluteFailed,
                 ));
             }
         }
 
-        for coin_spend in spend_bundle.coin_spends {
+        for coin_spend in &spend_bundle.coin_spends {
             puzzle_solutions.insert(
                 coin_spend.coin.coin_id(),
-                (coin_spend.puzzle_reveal, coin_spend.solution),
+                (
+                    coin_spend.puzzle_reveal.clone(),
+                    coin_spend.solution.clone(),
+                ),
             );
         }
 
@@ -222,8 +684,9 @@ impl Simulator {
 
                 added_coins.insert(
                     coin.coin_id(),
-                    CoinState::new(coin, None, Some(self.height)),
+                    CoinState::new(coin, None, Some(block_height)),
                 );
+                added_timestamps.insert(coin.coin_id(), self.timestamp);
 
                 let Some(hint) = new_coin.2.clone() else {
                     continue;
@@ -241,11 +704,19 @@ impl Simulator {
 
             let coin = Coin::new(spend.parent_id, spend.puzzle_hash, spend.coin_amount);
 
-            let coin_state = self
-                .coin_states
+            let coin_state = block
+                .updates
+                .get(&spend.coin_id)
+                .or_else(|| self.coin_states.get(&spend.coin_id))
+                .copied()
+                .unwrap_or(CoinState::new(coin, None, Some(block_height)));
+
+            let created_timestamp = self
+                .coin_timestamps
                 .get(&spend.coin_id)
+                .or_else(|| block.timestamps.get(&spend.coin_id))
                 .copied()
-                .unwrap_or(CoinState::new(coin, None, Some(self.height)));
+                .unwrap_or(self.timestamp);
 
             if let Some(relative_height) = spend.height_relative {
                 let Some(created_height) = coin_state.created_height else {
@@ -254,22 +725,21 @@ impl Simulator {
                     ));
                 };
 
-                if self.height < created_height + relative_height {
+                if block_height < created_height + relative_height {
                     return Err(SimulatorError::Validation(
                         ErrorCode::AssertHeightRelativeFailed,
                     ));
                 }
             }
 
-            // TODO: Tick time differently?
             if let Some(relative_seconds) = spend.seconds_relative {
-                let Some(created_height) = coin_state.created_height else {
+                if coin_state.created_height.is_none() {
                     return Err(SimulatorError::Validation(
                         ErrorCode::EphemeralRelativeCondition,
                     ));
-                };
+                }
 
-                if u64::from(self.height) < u64::from(created_height) + relative_seconds {
+                if self.timestamp < created_timestamp + relative_seconds {
                     return Err(SimulatorError::Validation(
                         ErrorCode::AssertSecondsRelativeFailed,
                     ));
@@ -283,22 +753,21 @@ impl Simulator {
                     ));
                 };
 
-                if created_height + relative_height < self.height {
+                if created_height + relative_height <= block_height {
                     return Err(SimulatorError::Validation(
                         ErrorCode::AssertBeforeHeightRelativeFailed,
                     ));
                 }
             }
 
-            // TODO: Tick time differently?
             if let Some(relative_seconds) = spend.before_seconds_relative {
-                let Some(created_height) = coin_state.created_height else {
+                if coin_state.created_height.is_none() {
                     return Err(SimulatorError::Validation(
                         ErrorCode::EphemeralRelativeCondition,
                     ));
-                };
+                }
 
-                if u64::from(created_height) + relative_seconds < u64::from(self.height) {
+                if created_timestamp + relative_seconds <= self.timestamp {
                     return Err(SimulatorError::Validation(
                         ErrorCode::AssertBeforeSecondsRelativeFailed,
                     ));
@@ -310,9 +779,10 @@ impl Simulator {
 
         // Validate removals.
         for (coin_id, coin_state) in &mut removed_coins {
-            let height = self.height;
-
-            if !self.coin_states.contains_key(coin_id) && !added_coins.contains_key(coin_id) {
+            if !self.coin_states.contains_key(coin_id)
+                && !block.updates.contains_key(coin_id)
+                && !added_coins.contains_key(coin_id)
+            {
                 return Err(SimulatorError::Validation(ErrorCode::UnknownUnspent));
             }
 
@@ -320,18 +790,35 @@ impl Simulator {
                 return Err(SimulatorError::Validation(ErrorCode::DoubleSpend));
             }
 
-            coin_state.spent_height = Some(height);
+            coin_state.spent_height = Some(block_height);
         }
 
-        // Update the coin data.
-        let mut updates = added_coins.clone();
-        updates.extend(removed_coins);
+        block.updates.extend(added_coins);
+        block.updates.extend(removed_coins);
+        block.timestamps.extend(added_timestamps);
+        block.puzzle_and_solutions.extend(puzzle_solutions);
+
+        for (hint, coin_ids) in added_hints {
+            block.hints.entry(hint).or_default().extend(coin_ids);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new block and updates the coin data.
+    fn commit_block(&mut self, block: PendingBlock) -> IndexMap<Bytes32, CoinState> {
         self.create_block();
-        self.coin_states.extend(updates.clone());
-        self.hinted_coins.extend(added_hints.clone());
-        self.puzzle_and_solutions.extend(puzzle_solutions);
+        self.coin_states.extend(block.updates.clone());
+        self.coin_timestamps.extend(block.timestamps);
+        self.puzzle_and_solutions.extend(block.puzzle_and_solutions);
 
-        Ok(updates)
+        for (hint, coin_ids) in block.hints {
+            self.hinted_coins.entry(hint).or_default().extend(coin_ids);
+        }
+
+        self.evict_mempool_items();
+
+        block.updates
     }
 
     pub fn lookup_coin_ids(&self, coin_ids: &IndexSet<Bytes32>) -> Vec<CoinState> {
@@ -358,7 +845,9 @@ impl Simulator {
             for puzzle_hash in puzzle_hashes {
                 if let Some(hinted_coins) = self.hinted_coins.get(&puzzle_hash) {
                     for coin_id in hinted_coins {
-                        coin_states.insert(*coin_id, self.coin_states[coin_id]);
+                        if let Some(coin_state) = self.coin_states.get(coin_id) {
+                            coin_states.insert(*coin_id, *coin_state);
+                        }
                     }
                 }
             }
@@ -371,6 +860,443 @@ impl Simulator {
         let mut header_hash = [0; 32];
         self.rng.fill(&mut header_hash);
         self.header_hashes.push(header_hash.into());
+        self.block_timestamps.push(self.timestamp);
         self.height += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::Signature;
+    use chia_sdk_types::{
+        AssertBeforeHeightRelative, AssertBeforeSecondsRelative, AssertHeightAbsolute,
+        AssertHeightRelative, AssertSecondsAbsolute, AssertSecondsRelative, CreateCoin,
+    };
+
+    use clvm_traits::ToClvm;
+    use clvmr::{Allocator, NodePtr};
+
+    use crate::{to_program, to_puzzle};
+
+    use super::*;
+
+    fn spend_bundle(coin: Coin, conditions: impl ToClvm<Allocator>) -> anyhow::Result<SpendBundle> {
+        let (_, puzzle_reveal) = to_puzzle(1)?;
+        Ok(SpendBundle::new(
+            vec![CoinSpend::new(coin, puzzle_reveal, to_program(conditions)?)],
+            Signature::default(),
+        ))
+    }
+
+    #[test]
+    fn test_pass_time() {
+        let mut sim = Simulator::new();
+
+        sim.pass_blocks(5);
+        assert_eq!(sim.height(), 5);
+        assert_eq!(sim.timestamp(), 0);
+
+        sim.pass_time(100);
+        assert_eq!(sim.height(), 5);
+        assert_eq!(sim.timestamp(), 100);
+        assert_eq!(sim.timestamp_of(5), Some(0));
+
+        sim.pass_blocks(1);
+        assert_eq!(sim.timestamp_of(6), Some(100));
+    }
+
+    #[test]
+    fn test_seconds_relative() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        sim.pass_time(1000);
+        let coin = sim.new_coin(puzzle_hash, 0);
+        assert_eq!(sim.created_timestamp(coin.coin_id()), Some(1000));
+
+        let bundle = spend_bundle(coin, [AssertSecondsRelative::new(100)])?;
+
+        // Passing blocks doesn't pass time.
+        sim.pass_blocks(200);
+        assert!(matches!(
+            sim.new_transaction(bundle.clone()),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertSecondsRelativeFailed
+            ))
+        ));
+
+        sim.pass_time(99);
+        assert!(matches!(
+            sim.new_transaction(bundle.clone()),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertSecondsRelativeFailed
+            ))
+        ));
+
+        sim.pass_time(1);
+        sim.new_transaction(bundle)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_before_seconds_relative() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 0);
+        let bundle = spend_bundle(coin, [AssertBeforeSecondsRelative::new(100)])?;
+
+        sim.pass_time(100);
+        assert!(matches!(
+            sim.new_transaction(bundle.clone()),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertBeforeSecondsRelativeFailed
+            ))
+        ));
+
+        let coin = sim.new_coin(puzzle_hash, 0);
+        sim.pass_time(99);
+        sim.new_transaction(spend_bundle(coin, [AssertBeforeSecondsRelative::new(100)])?)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seconds_absolute() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 0);
+        let bundle = spend_bundle(coin, [AssertSecondsAbsolute::new(500)])?;
+
+        sim.pass_time(499);
+        assert!(matches!(
+            sim.new_transaction(bundle.clone()),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertSecondsAbsoluteFailed
+            ))
+        ));
+
+        sim.pass_time(1);
+        sim.new_transaction(bundle)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_mode() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        sim.set_mempool_mode(true);
+
+        let (puzzle_hash, _) = to_puzzle(1)?;
+        let coin = sim.new_coin(puzzle_hash, 0);
+        let bundle = spend_bundle(coin, ())?;
+
+        // The spend bundle is queued rather than included in a block.
+        assert_eq!(
+            sim.new_transaction(bundle.clone())?,
+            TransactionResult::Queued(MempoolInclusionStatus::Success)
+        );
+        assert_eq!(sim.height(), 0);
+        assert_eq!(sim.mempool_ids(), vec![bundle.name()]);
+
+        assert_eq!(sim.farm_block()?.len(), 1);
+        assert!(sim
+            .coin_state(coin.coin_id())
+            .unwrap()
+            .spent_height
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_height_relative() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        // Coins minted directly are created at the current height, so the next block is 1 later.
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let bundle = spend_bundle(coin, [AssertHeightRelative::new(2)])?;
+
+        assert!(matches!(
+            sim.new_transaction(bundle.clone()),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertHeightRelativeFailed
+            ))
+        ));
+
+        sim.pass_blocks(1);
+        sim.new_transaction(bundle)?;
+
+        // Coins created by a transaction can be spent with a relative height of 1 in the next block.
+        let coin = sim.new_coin(puzzle_hash, 1);
+        sim.new_transaction(spend_bundle(
+            coin,
+            [CreateCoin::<NodePtr>::new(puzzle_hash, 1, None)],
+        )?)?;
+
+        let child = Coin::new(coin.coin_id(), puzzle_hash, 1);
+        assert_eq!(
+            sim.coin_state(child.coin_id()).unwrap().created_height,
+            Some(sim.height())
+        );
+        sim.new_transaction(spend_bundle(child, [AssertHeightRelative::new(1)])?)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_before_height_relative() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        // The next block is 1 after the coin was created, which is before the relative height of 2.
+        let coin = sim.new_coin(puzzle_hash, 1);
+        sim.new_transaction(spend_bundle(coin, [AssertBeforeHeightRelative::new(2)])?)?;
+
+        let coin = sim.new_coin(puzzle_hash, 2);
+        sim.pass_blocks(1);
+
+        assert!(matches!(
+            sim.new_transaction(spend_bundle(coin, [AssertBeforeHeightRelative::new(2)])?),
+            Err(SimulatorError::Validation(
+                ErrorCode::AssertBeforeHeightRelativeFailed
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_farm_block() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 1000);
+        let child = Coin::new(coin.coin_id(), puzzle_hash, 900);
+
+        let parent_bundle =
+            spend_bundle(coin, [CreateCoin::<NodePtr>::new(puzzle_hash, 900, None)])?;
+        let child_bundle = spend_bundle(child, ())?;
+
+        assert_eq!(
+            sim.submit_transaction(parent_bundle)?,
+            MempoolInclusionStatus::Success
+        );
+        assert!(matches!(
+            sim.submit_transaction(spend_bundle(Coin::new(coin.coin_id(), puzzle_hash, 1), ())?),
+            Err(SimulatorError::Validation(ErrorCode::UnknownUnspent))
+        ));
+
+        // The child spend depends on the unconfirmed parent spend, but pays a higher fee.
+        assert_eq!(
+            sim.submit_transaction(child_bundle)?,
+            MempoolInclusionStatus::Success
+        );
+        assert_eq!(sim.mempool_ids().len(), 2);
+
+        // Nothing is confirmed until a block is farmed.
+        assert_eq!(sim.coin_state(coin.coin_id()).unwrap().spent_height, None);
+
+        let updates = sim.farm_block()?;
+        assert_eq!(sim.height(), 1);
+        assert!(sim.mempool_ids().is_empty());
+        assert_eq!(updates[&coin.coin_id()].spent_height, Some(1));
+        assert_eq!(updates[&child.coin_id()].spent_height, Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_replace_by_fee() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let amount = 1_000_000_000;
+        let coin = sim.new_coin(puzzle_hash, amount);
+
+        let with_fee = |fee: u64| {
+            spend_bundle(
+                coin,
+                [CreateCoin::<NodePtr>::new(puzzle_hash, amount - fee, None)],
+            )
+        };
+
+        let original = with_fee(1_000_000)?;
+        sim.submit_transaction(original.clone())?;
+
+        // The fee must increase by at least the minimum fee increase.
+        for fee in [500_000, 1_000_000 + MEMPOOL_MIN_FEE_INCREASE - 1] {
+            assert!(matches!(
+                sim.submit_transaction(with_fee(fee)?),
+                Err(SimulatorError::MempoolConflict)
+            ));
+        }
+
+        let replacement = with_fee(1_000_000 + MEMPOOL_MIN_FEE_INCREASE)?;
+        sim.submit_transaction(replacement.clone())?;
+        assert_eq!(sim.mempool_ids(), vec![replacement.name()]);
+        assert_eq!(sim.mempool_item(original.name()), None);
+
+        sim.farm_block()?;
+        assert_eq!(
+            sim.coin_state(Coin::new(coin.coin_id(), puzzle_hash, amount - 11_000_000).coin_id())
+                .unwrap()
+                .created_height,
+            Some(1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_pending() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 0);
+        let bundle = spend_bundle(coin, [AssertHeightAbsolute::new(3)])?;
+
+        assert_eq!(
+            sim.submit_transaction(bundle.clone())?,
+            MempoolInclusionStatus::Pending
+        );
+
+        // The timelock hasn't passed yet, so the item stays in the mempool.
+        assert!(sim.farm_block()?.is_empty());
+        assert_eq!(sim.mempool_ids(), vec![bundle.name()]);
+
+        sim.pass_blocks(2);
+        assert_eq!(sim.farm_block()?.len(), 1);
+        assert!(sim.mempool_ids().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_eviction() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 0);
+        sim.submit_transaction(spend_bundle(coin, [AssertHeightAbsolute::new(100)])?)?;
+
+        // Spending the coin directly invalidates the mempool item.
+        sim.new_transaction(spend_bundle(coin, ())?)?;
+        assert!(sim.mempool_ids().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 1);
+        let child = Coin::new(coin.coin_id(), puzzle_hash, 1);
+
+        // Coins minted directly are part of the current peak.
+        sim.pass_blocks(1);
+        let minted = sim.new_coin(puzzle_hash, 2);
+        let header_hash = sim.header_hash();
+
+        sim.new_transaction(spend_bundle(
+            coin,
+            [CreateCoin::<NodePtr>::new(puzzle_hash, 1, None)],
+        )?)?;
+        sim.hint_coin(child.coin_id(), puzzle_hash);
+        assert_eq!(sim.height(), 2);
+        assert_eq!(
+            sim.coin_state(coin.coin_id()).unwrap().spent_height,
+            Some(2)
+        );
+        assert_eq!(
+            sim.coin_state(child.coin_id()).unwrap().created_height,
+            Some(2)
+        );
+
+        // Rolling back to the current height keeps every block.
+        assert!(sim.rollback_to(sim.height()).is_empty());
+        assert_eq!(sim.height(), 2);
+        assert!(sim.coin_state(child.coin_id()).is_some());
+
+        // Only the last block is undone, which spent the coin.
+        let updates = sim.rollback_to(1);
+        assert_eq!(sim.height(), 1);
+        assert_eq!(sim.header_hash(), header_hash);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(
+            updates[&coin.coin_id()],
+            CoinState::new(coin, None, Some(0))
+        );
+        assert_eq!(updates[&child.coin_id()], CoinState::new(child, None, None));
+
+        assert_eq!(sim.coin_state(coin.coin_id()).unwrap().spent_height, None);
+        assert_eq!(sim.coin_state(child.coin_id()), None);
+        assert_eq!(sim.puzzle_reveal(coin.coin_id()), None);
+        assert!(sim.children(coin.coin_id()).is_empty());
+        assert!(sim.hinted_coins(puzzle_hash).is_empty());
+
+        // The coin minted at the rollback height is kept.
+        assert_eq!(
+            sim.coin_state(minted.coin_id()),
+            Some(CoinState::new(minted, None, Some(1)))
+        );
+
+        // Rolling back further removes it, but keeps the coin minted at height 0.
+        let updates = sim.rollback_to(0);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[&minted.coin_id()],
+            CoinState::new(minted, None, None)
+        );
+        assert_eq!(sim.coin_state(minted.coin_id()), None);
+        assert!(sim.coin_state(coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_switch_peak() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, _) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 2);
+        let child = Coin::new(coin.coin_id(), puzzle_hash, 1);
+        let other_child = Coin::new(coin.coin_id(), puzzle_hash, 2);
+
+        sim.pass_blocks(1);
+        sim.new_transaction(spend_bundle(
+            coin,
+            [CreateCoin::<NodePtr>::new(puzzle_hash, 1, None)],
+        )?)?;
+
+        // The fork spends the coin a block later, so its spent height changes.
+        let mut fork = sim.fork(1);
+        fork.pass_blocks(1);
+        fork.new_transaction(spend_bundle(
+            coin,
+            [CreateCoin::<NodePtr>::new(puzzle_hash, 2, None)],
+        )?)?;
+        fork.pass_blocks(2);
+        assert_eq!(fork.header_hash_of(1), sim.header_hash_of(1));
+        assert_ne!(fork.header_hash_of(2), sim.header_hash_of(2));
+
+        // The last block both chains have in common is the fork height.
+        let (fork_height, updates) = sim.switch_peak(fork);
+        assert_eq!(fork_height, 1);
+        assert_eq!(sim.height(), 5);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[&coin.coin_id()].spent_height, Some(3));
+        assert_eq!(updates[&child.coin_id()], CoinState::new(child, None, None));
+        assert_eq!(
+            updates[&other_child.coin_id()],
+            CoinState::new(other_child, None, Some(3))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fs, io::Cursor, path::Path};
+
+use chia_protocol::{Bytes32, CoinState, Program, SpendBundle};
+use chia_traits::Streamable;
+use fastrand::Rng;
+use indexmap::{IndexMap, IndexSet};
+
+use crate::SimulatorError;
+
+use super::Simulator;
+
+/// The version of the snapshot encoding, which is prepended to the serialized state.
+const SNAPSHOT_VERSION: u8 = 1;
+
+impl Simulator {
+    /// Serializes the full state of the simulator, including the mempool, using the streamable format.
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, SimulatorError> {
+        let mut output = Vec::new();
+
+        SNAPSHOT_VERSION.stream(&mut output)?;
+        self.rng.get_seed().stream(&mut output)?;
+        self.height.stream(&mut output)?;
+        self.header_hashes.stream(&mut output)?;
+        self.timestamp.stream(&mut output)?;
+        self.block_timestamps.stream(&mut output)?;
+
+        let coin_states: Vec<CoinState> = self.coin_states.values().copied().collect();
+        coin_states.stream(&mut output)?;
+
+        let hinted_coins: Vec<(Bytes32, Vec<Bytes32>)> = self
+            .hinted_coins
+            .iter()
+            .map(|(hint, coin_ids)| (*hint, coin_ids.iter().copied().collect()))
+            .collect();
+        hinted_coins.stream(&mut output)?;
+
+        let coin_timestamps: Vec<(Bytes32, u64)> = self
+            .coin_timestamps
+            .iter()
+            .map(|(coin_id, timestamp)| (*coin_id, *timestamp))
+            .collect();
+        coin_timestamps.stream(&mut output)?;
+
+        let puzzle_and_solutions: Vec<(Bytes32, (Program, Program))> = self
+            .puzzle_and_solutions
+            .iter()
+            .map(|(coin_id, puzzle_and_solution)| (*coin_id, puzzle_and_solution.clone()))
+            .collect();
+        puzzle_and_solutions.stream(&mut output)?;
+
+        let mempool: Vec<SpendBundle> = self
+            .mempool
+            .values()
+            .map(|item| item.spend_bundle.clone())
+            .collect();
+        mempool.stream(&mut output)?;
+
+        Ok(output)
+    }
+
+    /// Restores a simulator from a snapshot created with [`Simulator::to_snapshot`].
+    /// The spend bundles in the mempool are validated again when they are restored, and mempool mode is disabled.
+    pub fn from_snapshot(snapshot: &[u8]) -> Result<Self, SimulatorError> {
+        let mut input = Cursor::new(snapshot);
+
+        let version = u8::parse::<false>(&mut input)?;
+
+        if version != SNAPSHOT_VERSION {
+            return Err(SimulatorError::UnsupportedSnapshotVersion(version));
+        }
+
+        let seed = u64::parse::<false>(&mut input)?;
+        let height = u32::parse::<false>(&mut input)?;
+        let header_hashes = Vec::<Bytes32>::parse::<false>(&mut input)?;
+        let timestamp = u64::parse::<false>(&mut input)?;
+        let block_timestamps = Vec::<u64>::parse::<false>(&mut input)?;
+        let coin_states = Vec::<CoinState>::parse::<false>(&mut input)?;
+        let hinted_coins = Vec::<(Bytes32, Vec<Bytes32>)>::parse::<false>(&mut input)?;
+        let coin_timestamps = Vec::<(Bytes32, u64)>::parse::<false>(&mut input)?;
+        let puzzle_and_solutions =
+            Vec::<(Bytes32, (Program, Program))>::parse::<false>(&mut input)?;
+        let mempool = Vec::<SpendBundle>::parse::<false>(&mut input)?;
+
+        if input.position() != snapshot.len() as u64 {
+            return Err(chia_traits::Error::InputTooLarge.into());
+        }
+
+        let mut simulator = Self {
+            rng: Rng::with_seed(seed),
+            height,
+            header_hashes,
+            timestamp,
+            block_timestamps,
+            coin_states: coin_states
+                .into_iter()
+                .map(|coin_state| (coin_state.coin.coin_id(), coin_state))
+                .collect(),
+            hinted_coins: hinted_coins
+                .into_iter()
+                .map(|(hint, coin_ids)| (hint, coin_ids.into_iter().collect::<IndexSet<_>>()))
+                .collect(),
+            coin_timestamps: coin_timestamps.into_iter().collect(),
+            puzzle_and_solutions: puzzle_and_solutions.into_iter().collect(),
+            mempool: IndexMap::new(),
+            mempool_mode: false,
+        };
+
+        for spend_bundle in mempool {
+            simulator.submit_transaction(spend_bundle)?;
+        }
+
+        Ok(simulator)
+    }
+
+    /// Writes a snapshot of the simulator to a file. See [`Simulator::to_snapshot`].
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SimulatorError> {
+        fs::write(path, self.to_snapshot()?)?;
+        Ok(())
+    }
+
+    /// Loads a simulator from a snapshot file. See [`Simulator::from_snapshot`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SimulatorError> {
+        Self::from_snapshot(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::Signature;
+    use chia_protocol::{Coin, CoinSpend};
+    use chia_sdk_types::{CreateCoin, Memos};
+    use clvm_traits::{FromClvm, ToClvm};
+    use clvmr::{Allocator, NodePtr};
+
+    use crate::{to_program, to_puzzle};
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let (puzzle_hash, puzzle_reveal) = to_puzzle(1)?;
+
+        let coin = sim.new_coin(puzzle_hash, 2);
+        let hint = Bytes32::new([42; 32]);
+
+        let mut allocator = Allocator::new();
+        let memos = Memos::hint(&mut allocator, hint)?;
+        let conditions = [CreateCoin::new(puzzle_hash, 1, Some(memos))].to_clvm(&mut allocator)?;
+
+        sim.new_transaction(SpendBundle::new(
+            vec![CoinSpend::new(
+                coin,
+                puzzle_reveal.clone(),
+                Program::from_clvm(&allocator, conditions)?,
+            )],
+            Signature::default(),
+        ))?;
+        sim.pass_time(1000);
+
+        let child = Coin::new(coin.coin_id(), puzzle_hash, 1);
+        sim.submit_transaction(SpendBundle::new(
+            vec![CoinSpend::new(
+                child,
+                puzzle_reveal,
+                to_program([CreateCoin::<NodePtr>::new(puzzle_hash, 1, None)])?,
+            )],
+            Signature::default(),
+        ))?;
+
+        let snapshot = sim.to_snapshot()?;
+        let mut restored = Simulator::from_snapshot(&snapshot)?;
+        assert_eq!(restored, sim);
+        assert_eq!(restored.hinted_coins(hint), vec![child.coin_id()]);
+        assert_eq!(restored.timestamp(), 1000);
+        assert_eq!(restored.mempool_ids().len(), 1);
+
+        // The restored simulator produces the same blocks.
+        restored.farm_block()?;
+        sim.farm_block()?;
+        assert_eq!(restored, sim);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_file() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let coin = sim.new_coin(Bytes32::default(), 1);
+        sim.pass_blocks(5);
+
+        let path = std::env::temp_dir().join(format!("simulator-{}.bin", sim.header_hash()));
+        sim.save(&path)?;
+        let restored = Simulator::load(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(restored, sim);
+        assert_eq!(
+            restored.coin_state(coin.coin_id()),
+            sim.coin_state(coin.coin_id())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_invalid() {
+        let sim = Simulator::new();
+        let mut snapshot = sim.to_snapshot().unwrap();
+
+        snapshot.push(0);
+        assert!(Simulator::from_snapshot(&snapshot).is_err());
+
+        snapshot[0] = 2;
+        assert!(matches!(
+            Simulator::from_snapshot(&snapshot),
+            Err(SimulatorError::UnsupportedSnapshotVersion(2))
+        ));
+
+        assert!(Simulator::from_snapshot(&[]).is_err());
+    }
+}
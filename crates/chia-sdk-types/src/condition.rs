@@ -195,6 +195,10 @@ conditions! {
     }
 }
 
+/// The mode of a message whose sender is committed to by puzzle hash,
+/// and whose receiver is committed to by coin id.
+pub const PUZZLE_TO_COIN_MESSAGE_MODE: u8 = 0b010_111;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
 #[clvm(list)]
 pub struct Memos<T> {
@@ -1,4 +1,6 @@
 mod augmented_condition;
+mod delegated_tail;
+mod genesis_by_puzzle_hash_tail;
 mod p2_curried;
 mod p2_delegated_conditions;
 mod p2_delegated_singleton;
@@ -6,6 +8,8 @@ mod p2_one_of_many;
 mod p2_singleton;
 
 pub use augmented_condition::*;
+pub use delegated_tail::*;
+pub use genesis_by_puzzle_hash_tail::*;
 pub use p2_curried::*;
 pub use p2_delegated_conditions::*;
 pub use p2_delegated_singleton::*;
@@ -0,0 +1,70 @@
+use chia_bls::PublicKey;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::TreeHash;
+use hex_literal::hex;
+
+use crate::Mod;
+
+/// The delegated TAIL allows issuance or melting of a CAT whenever the owner of the public key
+/// signs the tree hash of a delegated puzzle. The delegated puzzle is then run with the
+/// same arguments as the TAIL itself, so it can enforce its own rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct DelegatedTailArgs {
+    pub public_key: PublicKey,
+}
+
+impl DelegatedTailArgs {
+    pub fn new(public_key: PublicKey) -> Self {
+        Self { public_key }
+    }
+}
+
+impl Mod for DelegatedTailArgs {
+    const MOD_REVEAL: &[u8] = &DELEGATED_TAIL_PUZZLE;
+    const MOD_HASH: TreeHash = DELEGATED_TAIL_PUZZLE_HASH;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct DelegatedTailSolution<P, S> {
+    pub delegated_puzzle: P,
+    pub delegated_solution: S,
+}
+
+impl<P, S> DelegatedTailSolution<P, S> {
+    pub fn new(delegated_puzzle: P, delegated_solution: S) -> Self {
+        Self {
+            delegated_puzzle,
+            delegated_solution,
+        }
+    }
+}
+
+pub const DELEGATED_TAIL_PUZZLE: [u8; 180] = hex!(
+    "
+    ff02ffff01ff04ffff04ff04ffff04ff05ffff04ffff02ff06ffff04ff02ffff
+    04ff82027fff80808080ff80808080ffff02ff82027fffff04ff0bffff04ff17
+    ffff04ff2fffff04ff5fffff04ff81bfff82057f80808080808080ffff04ffff
+    01ff31ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff06ffff04ff
+    02ffff04ff09ff80808080ffff02ff06ffff04ff02ffff04ff0dff8080808080
+    ffff01ff0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const DELEGATED_TAIL_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "999c3696e167f8a79d938adc11feba3a3dcb39ccff69a426d570706e7b8ec399"
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_puzzle_hash;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(DELEGATED_TAIL_PUZZLE => DELEGATED_TAIL_PUZZLE_HASH);
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use chia_protocol::Bytes32;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::TreeHash;
+use hex_literal::hex;
+
+use crate::Mod;
+
+/// The genesis by puzzle hash TAIL allows a CAT to be issued by any coin whose parent has a given puzzle hash.
+/// The CAT can't be melted or issued any other way, since the delta must be zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct GenesisByPuzzleHashTailArgs {
+    pub genesis_puzzle_hash: Bytes32,
+}
+
+impl GenesisByPuzzleHashTailArgs {
+    pub fn new(genesis_puzzle_hash: Bytes32) -> Self {
+        Self {
+            genesis_puzzle_hash,
+        }
+    }
+}
+
+impl Mod for GenesisByPuzzleHashTailArgs {
+    const MOD_REVEAL: &[u8] = &GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE;
+    const MOD_HASH: TreeHash = GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH;
+}
+
+/// Reveals the parent coin of the eve CAT, so that its puzzle hash can be checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct GenesisByPuzzleHashTailSolution {
+    pub parent_parent_coin_info: Bytes32,
+    pub parent_amount: u64,
+}
+
+impl GenesisByPuzzleHashTailSolution {
+    pub fn new(parent_parent_coin_info: Bytes32, parent_amount: u64) -> Self {
+        Self {
+            parent_parent_coin_info,
+            parent_amount,
+        }
+    }
+}
+
+pub const GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE: [u8; 59] = hex!(
+    "
+    ff02ffff03ff2fffff01ff0880ffff01ff02ffff03ffff09ffff0bff82013fff
+    02ff8202bf80ff2d80ffff0180ffff01ff088080ff018080ff0180
+    "
+);
+
+pub const GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "a6347d5b25465beafd8931cfaa94d1feca6e380df0e069510fe399d763c963bb"
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_puzzle_hash;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE => GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH);
+        Ok(())
+    }
+}
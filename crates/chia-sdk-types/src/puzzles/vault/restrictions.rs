@@ -1,10 +1,12 @@
 mod force_assert_coin_announcement;
 mod force_coin_message;
+mod rate_limit;
 mod recovery;
 mod timelock;
 
 pub use force_assert_coin_announcement::*;
 pub use force_coin_message::*;
+pub use rate_limit::*;
 pub use recovery::*;
 pub use timelock::*;
 
@@ -0,0 +1,128 @@
+use chia_protocol::Bytes32;
+use chia_puzzles::singleton::{SingletonStruct, SINGLETON_TOP_LAYER_PUZZLE_HASH};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{ToTreeHash, TreeHash};
+use hex_literal::hex;
+
+use crate::Mod;
+
+/// A delegated puzzle validator which limits how much a vault can spend at a time.
+///
+/// The delegated puzzle is revealed in the solution, and must be quoted conditions so that its
+/// output can't depend on the delegated solution. The source is in `rate_limit.clsp`.
+/// The conditions must:
+/// - Assert the amount and puzzle hash of the vault coin.
+/// - Recreate the vault with the same custody, and at most `limit` fewer mojos.
+/// - Assert that at least `window` seconds have passed since the vault coin was created.
+/// - Not create announcements or send messages, which could authorize other spends.
+///
+/// Since only one such spend can happen per window, the vault can't spend more than
+/// `limit` mojos every `window` seconds with the restricted member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct SpendRateLimit {
+    pub singleton_mod_hash: Bytes32,
+    pub singleton_struct_hash: Bytes32,
+    pub limit: u64,
+    pub window: u64,
+}
+
+impl SpendRateLimit {
+    pub fn new(launcher_id: Bytes32, limit: u64, window: u64) -> Self {
+        Self {
+            singleton_mod_hash: SINGLETON_TOP_LAYER_PUZZLE_HASH.into(),
+            singleton_struct_hash: SingletonStruct::new(launcher_id).tree_hash().into(),
+            limit,
+            window,
+        }
+    }
+}
+
+impl Mod for SpendRateLimit {
+    const MOD_REVEAL: &[u8] = &RATE_LIMIT_PUZZLE;
+    const MOD_HASH: TreeHash = RATE_LIMIT_PUZZLE_HASH;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(solution)]
+pub struct SpendRateLimitSolution<P> {
+    pub delegated_puzzle: P,
+}
+
+impl<P> SpendRateLimitSolution<P> {
+    pub fn new(delegated_puzzle: P) -> Self {
+        Self { delegated_puzzle }
+    }
+}
+
+pub const RATE_LIMIT_PUZZLE: [u8; 1595] = hex!(
+    "
+    ff02ffff01ff02ffff03ffff22ffff09ffff02ff04ffff04ff02ffff04ff8200
+    bfff80808080ff5f80ffff09ffff05ff8200bf80ffff01018080ffff01ff02ff
+    2effff04ff02ffff04ff05ffff04ff0bffff04ff17ffff04ff2fffff04ffff02
+    ff0affff04ff02ffff04ffff06ff8200bf80ffff04ff80ffff04ff80ffff04ff
+    80ffff04ff80ffff04ff80ff808080808080808080ff8080808080808080ffff
+    01ff088080ff0180ffff04ffff01ffff02ffff03ffff07ff0580ffff01ff0bff
+    ff0102ffff02ff04ffff04ff02ffff04ffff05ff0580ff80808080ffff02ff04
+    ffff04ff02ffff04ffff06ff0580ff8080808080ffff01ff0bffff0101ff0580
+    80ff0180ffff02ffff03ff05ffff01ff02ff16ffff04ff02ffff04ffff05ffff
+    05ff058080ffff04ffff06ffff05ff058080ffff04ffff06ff0580ffff04ff0b
+    ffff04ff17ffff04ff2fffff04ff5fffff04ff8200bfff808080808080808080
+    8080ffff01ff04ff0bffff04ff17ffff04ff2fffff04ff5fffff04ff8200bfff
+    80808080808080ff0180ffff02ffff03ffff09ff05ffff014980ffff01ff02ff
+    0affff04ff02ffff04ff17ffff04ffff05ff0b80ffff04ff5fffff04ff8200bf
+    ffff04ff82017fffff04ff8202ffff808080808080808080ffff01ff02ffff03
+    ffff09ff05ffff014880ffff01ff02ff0affff04ff02ffff04ff17ffff04ff2f
+    ffff04ffff05ff0b80ffff04ff8200bfffff04ff82017fffff04ff8202ffff80
+    8080808080808080ffff01ff02ffff03ffff09ff05ffff015080ffff01ff02ff
+    0affff04ff02ffff04ff17ffff04ff2fffff04ff5fffff04ffff02ffff03ffff
+    15ffff05ff0b80ff8200bf80ffff01ff05ff0b80ffff018200bf80ff0180ffff
+    04ff82017fffff04ff8202ffff808080808080808080ffff01ff02ffff03ffff
+    09ff05ffff013380ffff01ff02ffff03ffff18ffff05ffff06ff0b8080ffff01
+    0180ffff01ff02ffff03ff82017fffff01ff0880ffff01ff02ff0affff04ff02
+    ffff04ff17ffff04ff2fffff04ff5fffff04ff8200bfffff04ffff05ff0b80ff
+    ff04ffff05ffff06ff0b8080ff80808080808080808080ff0180ffff01ff02ff
+    0affff04ff02ffff04ff17ffff04ff2fffff04ff5fffff04ff8200bfffff04ff
+    82017fffff04ff8202ffff80808080808080808080ff0180ffff01ff02ffff03
+    ffff21ffff09ff05ffff013c80ffff09ff05ffff013e80ffff09ff05ffff0142
+    8080ffff01ff0880ffff01ff02ff0affff04ff02ffff04ff17ffff04ff2fffff
+    04ff5fffff04ff8200bfffff04ff82017fffff04ff8202ffff80808080808080
+    808080ff018080ff018080ff018080ff018080ff0180ffff02ff5effff04ff02
+    ffff04ff05ffff04ff0bffff04ff17ffff04ff2fffff04ffff05ff5f80ffff04
+    ffff05ffff06ff5f8080ffff04ffff05ffff06ffff06ff5f808080ffff04ffff
+    05ffff06ffff06ffff06ff5f80808080ffff04ffff05ffff06ffff06ffff06ff
+    ff06ff5f8080808080ff808080808080808080808080ffff02ffff03ffff22ff
+    5fff8202ffffff09ff8200bfffff02ff82017effff04ff02ffff04ff05ffff04
+    ff0bffff04ff8202ffff80808080808080ffff20ffff15ff2fff82017f8080ff
+    ff20ffff15ffff11ff5fff8205ff80ff17808080ffff0180ffff01ff088080ff
+    0180ffff0bffff0102ffff01a09dcf97a184f32623d11a73124ceb99a5709b08
+    3721e878a16d78f596718ba7b2ff0580ffff0bffff0102ffff01a0a12871fee2
+    10fb8619291eaea194581cbd2531e4b23759d225f6806923f63222ffff0bffff
+    0102ffff02ff8200beffff04ff02ffff04ff05ff80808080ffff0bffff0102ff
+    ff02ff8202feffff04ff02ffff04ff0bffff04ffff02ff8202feffff04ff02ff
+    ff04ff17ffff04ffff01a09dcf97a184f32623d11a73124ceb99a5709b083721
+    e878a16d78f596718ba7b2ff8080808080ff8080808080ffff01a04bf5122f34
+    4554c53bde2ebb8cd2b7e3d1600ad631c385a5d7cce23c7785459a808080ffff
+    0bffff0102ffff01a0a8d5dd63fba471ebcb1f3e8f7c1e1879b7152a6e7298a9
+    1ce119a63400ade7c5ffff0bffff0102ffff02ff8200beffff04ff02ffff04ff
+    05ff80808080ffff0bffff0102ff0bffff01a04bf5122f344554c53bde2ebb8c
+    d2b7e3d1600ad631c385a5d7cce23c7785459a80808080ff018080
+    "
+);
+
+pub const RATE_LIMIT_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "603ef1496d57be41b10dcaa532bef78489e02a3c977f570a87e35e18f1d2f414"
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_puzzle_hash;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(RATE_LIMIT_PUZZLE => RATE_LIMIT_PUZZLE_HASH);
+        Ok(())
+    }
+}
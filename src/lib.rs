@@ -7,3 +7,9 @@ pub use chia_sdk_signer::*;
 pub use chia_sdk_test::*;
 pub use chia_sdk_types::*;
 pub use chia_sdk_utils::*;
+
+#[cfg(feature = "experimental-vaults")]
+mod vault_sync;
+
+#[cfg(feature = "experimental-vaults")]
+pub use vault_sync::*;
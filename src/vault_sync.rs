@@ -0,0 +1,145 @@
+use chia_protocol::{Bytes32, RejectStateReason};
+use chia_sdk_client::{ClientError, Peer};
+use chia_sdk_driver::{DriverError, Puzzle, SpendContext, Vault};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VaultSyncError {
+    #[error("client error: {0}")]
+    Client(#[from] ClientError),
+
+    #[error("driver error: {0}")]
+    Driver(#[from] DriverError),
+
+    #[error("coin state request was rejected: {0:?}")]
+    CoinStateRejected(RejectStateReason),
+
+    #[error("missing coin state for coin {0}")]
+    MissingCoinState(Bytes32),
+
+    #[error("puzzle and solution request was rejected for coin {0}")]
+    PuzzleSolutionRejected(Bytes32),
+}
+
+/// Follows the lineage of a vault from the given coin to its latest coin, by requesting
+/// the puzzle and solution of each spent coin from the peer and parsing its child.
+///
+/// Returns [`None`] if the vault has been melted.
+pub async fn sync_vault(
+    peer: &Peer,
+    genesis_challenge: Bytes32,
+    mut vault: Vault,
+) -> Result<Option<Vault>, VaultSyncError> {
+    let mut ctx = SpendContext::new();
+
+    loop {
+        let coin_id = vault.coin.coin_id();
+
+        let response = peer
+            .request_coin_state(vec![coin_id], None, genesis_challenge, false)
+            .await?
+            .map_err(|rejection| VaultSyncError::CoinStateRejected(rejection.reason))?;
+
+        let coin_state = response
+            .coin_states
+            .into_iter()
+            .find(|coin_state| coin_state.coin.coin_id() == coin_id)
+            .ok_or(VaultSyncError::MissingCoinState(coin_id))?;
+
+        let Some(spent_height) = coin_state.spent_height else {
+            return Ok(Some(vault));
+        };
+
+        let response = peer
+            .request_puzzle_and_solution(coin_id, spent_height)
+            .await?
+            .map_err(|_| VaultSyncError::PuzzleSolutionRejected(coin_id))?;
+
+        let puzzle = ctx.alloc(&response.puzzle)?;
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle);
+        let solution = ctx.alloc(&response.solution)?;
+
+        let Some(child) = Vault::parse_child(&mut ctx.allocator, vault.coin, puzzle, solution)?
+        else {
+            return Ok(None);
+        };
+
+        vault = child;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chia_puzzles::standard::StandardArgs;
+    use chia_sdk_driver::{CustodyConfig, CustodyMember, Launcher, StandardLayer};
+    use chia_sdk_test::{test_secret_key, test_transaction, PeerSimulator};
+    use chia_sdk_types::{Conditions, MeltSingleton};
+    use clvmr::NodePtr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sync_vault() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let genesis_challenge = sim.config().constants.genesis_challenge;
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let pk = sk.public_key();
+        let p2 = StandardLayer::new(pk);
+        let coin = sim
+            .mint_coin(StandardArgs::curry_tree_hash(pk).into(), 1)
+            .await;
+
+        let mut custody = CustodyConfig::new(CustodyMember::Bls { public_key: pk });
+
+        let (mint_vault, launched) =
+            Launcher::new(coin.coin_id(), 1).mint_vault(ctx, custody.custody_hash(), ())?;
+        p2.spend(ctx, coin, mint_vault)?;
+        test_transaction(&peer, ctx.take(), &[sk.clone()]).await;
+
+        assert_eq!(
+            sync_vault(&peer, genesis_challenge, launched).await?,
+            Some(launched)
+        );
+
+        // Rotate the custody a few times, so there are several spends to follow
+        let mut vault = launched;
+
+        for nonce in 1..=3 {
+            let new_custody = custody.clone().with_nonce(nonce);
+            let conditions =
+                Conditions::new().create_coin(new_custody.custody_hash().into(), 1, None);
+            let delegated_spend = ctx.delegated_spend(conditions)?;
+            let solutions = HashMap::from([(custody.leaves()[0].0, NodePtr::NIL)]);
+            let spend = custody.vault_spend(ctx, delegated_spend, &solutions)?;
+
+            vault.spend(ctx, &spend)?;
+            test_transaction(&peer, ctx.take(), &[sk.clone()]).await;
+
+            vault = vault.child(new_custody.custody_hash());
+            custody = new_custody;
+        }
+
+        assert_eq!(
+            sync_vault(&peer, genesis_challenge, launched).await?,
+            Some(vault)
+        );
+
+        // Once the vault is melted, there's no child to sync to
+        let conditions = Conditions::new().with(MeltSingleton::default());
+        let delegated_spend = ctx.delegated_spend(conditions)?;
+        let solutions = HashMap::from([(custody.leaves()[0].0, NodePtr::NIL)]);
+        let spend = custody.vault_spend(ctx, delegated_spend, &solutions)?;
+
+        vault.spend(ctx, &spend)?;
+        test_transaction(&peer, ctx.take(), &[sk]).await;
+
+        assert_eq!(sync_vault(&peer, genesis_challenge, launched).await?, None);
+
+        Ok(())
+    }
+}